@@ -8,7 +8,11 @@ use crate::{
     Array, ArrayValue, FormatShape, Uiua, UiuaResult, Value,
 };
 
-fn data_index_to_shape_index(mut index: usize, shape: &[usize], out: &mut [usize]) -> bool {
+pub(super) fn data_index_to_shape_index(
+    mut index: usize,
+    shape: &[usize],
+    out: &mut [usize],
+) -> bool {
     debug_assert_eq!(shape.len(), out.len());
     if index >= shape.iter().product() {
         return false;
@@ -35,6 +39,31 @@ fn data_index_to_shape_index_test() {
     }
 }
 
+/// Get the row-major strides of a shape
+///
+/// The stride of an axis is the distance in the data between
+/// items whose indices differ by 1 along that axis.
+pub(super) fn shape_strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for i in (1..shape.len()).rev() {
+        strides[i - 1] = strides[i] * shape[i];
+    }
+    strides
+}
+
+#[test]
+fn shape_strides_test() {
+    assert_eq!(shape_strides(&[]), Vec::<usize>::new());
+    assert_eq!(shape_strides(&[4]), [1]);
+    assert_eq!(shape_strides(&[2, 3, 4]), [12, 4, 1]);
+    assert_eq!(shape_strides(&[2, 0, 4]), [0, 4, 1]);
+    for (index, shape) in [([1, 2], [2, 3]), ([0, 1], [1, 3])] {
+        let strides = shape_strides(&shape);
+        let data_index: usize = index.iter().zip(&strides).map(|(i, s)| i * s).sum();
+        assert_eq!(Some(data_index), shape_index_to_data_index(&index, &shape));
+    }
+}
+
 fn shape_index_to_data_index(index: &[usize], shape: &[usize]) -> Option<usize> {
     debug_assert_eq!(shape.len(), index.len());
     let mut data_index = 0;
@@ -5,14 +5,15 @@ mod structure;
 
 use std::{
     borrow::Cow,
+    cell::{Cell, RefCell},
     cmp::Ordering,
     collections::{hash_map::DefaultHasher, HashMap, HashSet},
     hash::{Hash, Hasher},
     iter::{once, repeat},
-    mem::take,
+    mem::{size_of, take},
 };
 
-use ecow::EcoVec;
+use ecow::{eco_vec, EcoVec};
 
 use crate::{
     array::*,
@@ -22,7 +23,10 @@ use crate::{
     Uiua, UiuaResult,
 };
 
-use super::{op_bytes_retry_fill, ArrayCmpSlice, FillContext};
+use combine::{data_index_to_shape_index, shape_strides};
+pub use structure::OobMode;
+
+use super::{for_each_chunk, op_bytes_retry_fill, ArrayCmpSlice, FillContext};
 
 impl Value {
     pub(crate) fn bin_coerce_to_boxes<T, C: FillContext, E: ToString>(
@@ -108,47 +112,253 @@ impl<T: Clone + std::fmt::Debug> Array<T> {
                 )));
             }
         }
+        // The number of times to cycle through `b`'s rows
+        // instead of physically replicating it
+        let mut b_repeat = 1;
+        match a_depth.cmp(&b_depth) {
+            Ordering::Equal => {}
+            Ordering::Less => {
+                for b_dim in b.shape[..b_depth - a_depth].iter().rev() {
+                    a.repeat_as_rows(*b_dim);
+                    a_depth += 1;
+                }
+            }
+            Ordering::Greater => b_repeat = a.shape[..a_depth - b_depth].iter().product(),
+        }
+
+        let a_row_shape = &a.shape[a_depth..];
+        let b_row_shape = &b.shape[b_depth..];
+        let b_rows = (b.data.as_slice()).chunks_exact(b_row_shape.iter().product());
+        let b_rows = b_rows.clone().cycle().take(b_rows.len() * b_repeat);
+        for (a, b) in (a.data.as_mut_slice())
+            .chunks_exact_mut(a_row_shape.iter().product())
+            .zip(b_rows)
+        {
+            f(a_row_shape, a, b_row_shape, b, ctx)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod depth_slices_props {
+    use quickcheck::{quickcheck, Arbitrary, Gen};
+
+    use super::*;
+
+    /// The original implementation of `depth_slices`, which replicates `b`
+    fn depth_slices_replicating(
+        a: &mut Array<f64>,
+        other: &Array<f64>,
+        mut a_depth: usize,
+        mut b_depth: usize,
+        env: &Uiua,
+        f: impl Fn(&[usize], &mut [f64], &[usize], &[f64], &Uiua) -> UiuaResult,
+    ) -> UiuaResult {
+        let mut b = other;
+        let mut local_b;
+        a_depth = a_depth.min(a.rank());
+        b_depth = b_depth.min(b.rank());
+        let a_prefix = &a.shape[..a_depth];
+        let b_prefix = &b.shape[..b_depth];
+        if !a_prefix.iter().zip(b_prefix).all(|(a, b)| a == b) {
+            while a.shape.starts_with(&[1]) {
+                if a_depth == 0 {
+                    break;
+                }
+                a.shape.remove(0);
+                a_depth -= 1;
+            }
+            if b.shape.starts_with(&[1]) {
+                local_b = b.clone();
+                while local_b.shape.starts_with(&[1]) {
+                    if b_depth == 0 {
+                        break;
+                    }
+                    local_b.shape.remove(0);
+                    b_depth -= 1;
+                }
+                b = &local_b;
+            }
+            let a_prefix = &a.shape[..a_depth];
+            let b_prefix = &b.shape[..b_depth];
+            if !a_prefix.iter().zip(b_prefix).all(|(a, b)| a == b) {
+                return Err(env.error("incompatible"));
+            }
+        }
         match a_depth.cmp(&b_depth) {
             Ordering::Equal => {}
             Ordering::Less => {
                 for b_dim in b.shape[..b_depth - a_depth].iter().rev() {
-                    a.reshape_scalar(*b_dim);
+                    a.repeat_as_rows(*b_dim);
                     a_depth += 1;
                 }
             }
             Ordering::Greater => {
                 for a_dim in a.shape[..a_depth - b_depth].iter().rev() {
                     local_b = b.clone();
-                    local_b.reshape_scalar(*a_dim);
+                    local_b.repeat_as_rows(*a_dim);
                     b = &local_b;
                     b_depth += 1;
                 }
             }
         }
-
         let a_row_shape = &a.shape[a_depth..];
         let b_row_shape = &b.shape[b_depth..];
         for (a, b) in (a.data.as_mut_slice())
             .chunks_exact_mut(a_row_shape.iter().product())
             .zip(b.data.as_slice().chunks_exact(b_row_shape.iter().product()))
         {
-            f(a_row_shape, a, b_row_shape, b, ctx)?;
+            f(a_row_shape, a, b_row_shape, b, env)?;
         }
         Ok(())
     }
+
+    #[derive(Debug, Clone)]
+    struct Case {
+        a_shape: Vec<usize>,
+        b_shape: Vec<usize>,
+        a_depth: usize,
+        b_depth: usize,
+    }
+
+    impl Case {
+        fn array(shape: &[usize], offset: usize) -> Array<f64> {
+            let len: usize = shape.iter().product();
+            Array::new(
+                shape.iter().copied().collect::<Shape>(),
+                (0..len)
+                    .map(|i| (i + offset) as f64)
+                    .collect::<CowSlice<_>>(),
+            )
+        }
+        fn is_valid(&self) -> bool {
+            // Rows of zero length can't be chunked
+            (self
+                .a_shape
+                .iter()
+                .skip(self.a_depth.min(self.a_shape.len())))
+            .all(|&d| d > 0)
+                && (self
+                    .b_shape
+                    .iter()
+                    .skip(self.b_depth.min(self.b_shape.len())))
+                .all(|&d| d > 0)
+        }
+    }
+
+    impl Arbitrary for Case {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let shape = |g: &mut Gen| -> Vec<usize> {
+                (0..usize::arbitrary(g) % 4)
+                    .map(|_| 1 + usize::arbitrary(g) % 3)
+                    .collect()
+            };
+            Case {
+                a_shape: shape(g),
+                b_shape: shape(g),
+                a_depth: usize::arbitrary(g) % 4,
+                b_depth: usize::arbitrary(g) % 4,
+            }
+        }
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            let tuple = (
+                self.a_shape.clone(),
+                self.b_shape.clone(),
+                self.a_depth,
+                self.b_depth,
+            );
+            Box::new(
+                tuple
+                    .shrink()
+                    .map(|(a_shape, b_shape, a_depth, b_depth)| Case {
+                        a_shape,
+                        b_shape,
+                        a_depth,
+                        b_depth,
+                    }),
+            )
+        }
+    }
+
+    #[test]
+    fn depth_slices_matches_replicating() {
+        fn prop(case: Case) -> bool {
+            if !case.is_valid() {
+                return true;
+            }
+            let env = Uiua::with_native_sys();
+            let b = Case::array(&case.b_shape, 100);
+            // Record which `b` row each `a` row is paired with
+            let f = |_: &[usize], a: &mut [f64], _: &[usize], b: &[f64], _: &Uiua| {
+                for (a, b) in a.iter_mut().zip(b.iter().cycle()) {
+                    *a = *a * 1000.0 + *b;
+                }
+                a[0] += b.len() as f64 * 1e6;
+                Ok(())
+            };
+            let mut expected = Case::array(&case.a_shape, 0);
+            let expected_res =
+                depth_slices_replicating(&mut expected, &b, case.a_depth, case.b_depth, &env, f);
+            let mut actual = Case::array(&case.a_shape, 0);
+            let actual_res = actual.depth_slices(&b, case.a_depth, case.b_depth, &env, f);
+            expected_res.is_ok() == actual_res.is_ok()
+                && (actual_res.is_err() || actual == expected)
+        }
+        quickcheck(prop as fn(Case) -> bool);
+    }
+
+    #[test]
+    fn uniform_rotate_depth_matches_general() {
+        fn prop(case: Case, amount: i8) -> bool {
+            let env = Uiua::with_native_sys();
+            let a_depth = case.a_depth.min(case.a_shape.len());
+            // Rotate by a scalar, or by the same amount repeated for every row
+            let by_rank = if case.b_depth % 2 == 0 { 0 } else { a_depth };
+            let by_shape: Shape = case.a_shape[..by_rank].iter().copied().collect();
+            let by_depth = if by_rank == 0 { case.b_depth } else { by_rank };
+            let by_len = by_shape.iter().product();
+            let by = Array::<isize>::new(by_shape, cowslice![amount as isize; by_len]);
+            if by_len > 0 && uniform_rotation(&by, by_depth, &case.a_shape, a_depth).is_none() {
+                return false;
+            }
+            let mut expected = Case::array(&case.a_shape, 0);
+            if expected.data.is_empty() {
+                return true;
+            }
+            expected
+                .rotate_depth(by.clone(), a_depth, by_depth, &env)
+                .unwrap();
+            let by = Value::from(Array::<f64>::new(
+                by.shape.clone(),
+                by.data.iter().map(|&n| n as f64).collect::<CowSlice<_>>(),
+            ));
+            let actual = by
+                .rotate_depth(
+                    Case::array(&case.a_shape, 0).into(),
+                    by_depth,
+                    a_depth,
+                    &env,
+                )
+                .unwrap();
+            actual == expected.into()
+        }
+        quickcheck(prop as fn(Case, i8) -> bool);
+    }
 }
 
 impl Value {
     /// `reshape` this value with another
     pub fn reshape(&mut self, shape: &Self, env: &Uiua) -> UiuaResult {
+        let shape = shape.unboxed_ref();
         if let Ok(n) = shape.as_nat(env, "") {
             match self {
-                Value::Num(a) => a.reshape_scalar(n),
+                Value::Num(a) => a.reshape_scalar(n, env)?,
                 #[cfg(feature = "bytes")]
-                Value::Byte(a) => a.reshape_scalar(n),
-                Value::Complex(a) => a.reshape_scalar(n),
-                Value::Char(a) => a.reshape_scalar(n),
-                Value::Box(a) => a.reshape_scalar(n),
+                Value::Byte(a) => a.reshape_scalar(n, env)?,
+                Value::Complex(a) => a.reshape_scalar(n, env)?,
+                Value::Char(a) => a.reshape_scalar(n, env)?,
+                Value::Box(a) => a.reshape_scalar(n, env)?,
             }
         } else {
             let target_shape = shape.as_ints(
@@ -180,6 +390,30 @@ impl Value {
         }
         Ok(())
     }
+    /// Get the shape that `reshape`ing this value to `dims` would produce
+    ///
+    /// A single negative dimension is derived from the number of elements.
+    /// If `has_fill` is set, it is rounded up to fit every element instead of down.
+    /// The value itself is not modified.
+    ///
+    /// ```
+    /// # use uiua::{Uiua, Value};
+    /// let env = Uiua::with_native_sys();
+    /// let value: Value = (0..7).map(|i| i as f64).collect();
+    /// let shape = value.derived_reshape_shape(&[-1, 2], false, &env).unwrap();
+    /// assert_eq!(&shape[..], [3, 2]);
+    /// let shape = value.derived_reshape_shape(&[-1, 2], true, &env).unwrap();
+    /// assert_eq!(&shape[..], [4, 2]);
+    /// assert!(value.derived_reshape_shape(&[-1, -1], false, &env).is_err());
+    /// ```
+    pub fn derived_reshape_shape(
+        &self,
+        dims: &[isize],
+        has_fill: bool,
+        env: &Uiua,
+    ) -> UiuaResult<Shape> {
+        derive_shape(self.shape(), dims, has_fill, env)
+    }
     pub(crate) fn unreshape(&mut self, old_shape: &Self, env: &Uiua) -> UiuaResult {
         if old_shape.as_nat(env, "").is_ok() {
             return Err(env.error("Cannot undo scalar reshae"));
@@ -201,28 +435,90 @@ impl Value {
 
 impl<T: Clone> Array<T> {
     /// `reshape` this array by replicating it as the rows of a new array
-    pub fn reshape_scalar(&mut self, count: usize) {
-        self.data.modify(|data| {
-            if count == 0 {
-                data.clear();
-                return;
-            }
-            data.reserve((count - 1) * data.len());
-            let row = data.clone();
-            for _ in 1..count {
-                data.extend_from_slice(&row);
-            }
-        });
+    ///
+    /// This errors if the result would be too large.
+    pub fn reshape_scalar(&mut self, count: usize, env: &Uiua) -> UiuaResult {
+        let shape = once(count).chain(self.shape.iter().copied());
+        checked_element_count::<T>(shape, "Reshape", env)?;
+        self.repeat_as_rows(count);
+        Ok(())
+    }
+    /// Replicate this array as the rows of a new array
+    ///
+    /// This is for broadcasting, where the count comes from another array's
+    /// shape, so the size of the result is not checked.
+    pub(crate) fn repeat_as_rows(&mut self, count: usize) {
+        if count == 0 {
+            self.data = CowSlice::new();
+        } else {
+            let len = self.data.len();
+            self.data.extend_from_within(0..len, count - 1);
+        }
         self.shape.insert(0, count);
     }
 }
 
+/// Get the number of elements in an array of the given shape
+///
+/// This errors if the array would be too large to allocate,
+/// or if it would have more elements than the environment's limit.
+pub(crate) fn checked_element_count<T>(
+    shape: impl IntoIterator<Item = usize>,
+    operation: &str,
+    env: &Uiua,
+) -> UiuaResult<usize> {
+    let shape: Vec<usize> = shape.into_iter().collect();
+    let max_len = isize::MAX as usize / size_of::<T>().max(1);
+    let limit = env
+        .rt
+        .element_limit
+        .map_or(max_len, |limit| limit.min(max_len));
+    let count = (shape.iter()).try_fold(1usize, |acc, &dim| acc.checked_mul(dim));
+    match count {
+        Some(count) if count <= limit => Ok(count),
+        _ => Err(env.error(format!(
+            "{operation} result too large: shape {} \
+            would have more than {limit} elements",
+            FormatShape(&shape)
+        ))),
+    }
+}
+
+/// Check that an operation that recurses once per axis stays within
+/// the environment's depth limit
+pub(crate) fn check_depth(depth: usize, operation: &str, env: &Uiua) -> UiuaResult {
+    let limit = env.rt.depth_limit;
+    if depth > limit {
+        Err(env.error(format!(
+            "{operation} exceeded maximum array nesting depth of {limit}"
+        )))
+    } else {
+        Ok(())
+    }
+}
+
 impl<T: ArrayValue> Array<T> {
     /// `reshape` the array
     pub fn reshape(&mut self, dims: &[isize], env: &Uiua) -> UiuaResult {
+        self.reshape_impl(dims, env).map(drop)
+    }
+    /// `reshape` this array, returning the elements that were cut off
+    ///
+    /// The remainder is a list, which is empty if the array was not shrunk.
+    pub fn reshape_with_remainder(&mut self, dims: &[isize], env: &Uiua) -> UiuaResult<Self> {
+        self.reshape_impl(dims, env).map(Into::into)
+    }
+    fn reshape_impl(&mut self, dims: &[isize], env: &Uiua) -> UiuaResult<CowSlice<T>> {
         let fill = env.fill::<T>();
+        // Checking the given dimensions first keeps their products from overflowing
+        let given_dims = dims
+            .iter()
+            .filter(|&&dim| dim >= 0)
+            .map(|&dim| dim as usize);
+        checked_element_count::<T>(given_dims, "Reshape", env)?;
         let shape = derive_shape(&self.shape, dims, fill.is_ok(), env)?;
-        let target_len: usize = shape.iter().product();
+        let target_len = checked_element_count::<T>(shape.iter().copied(), "Reshape", env)?;
+        let mut remainder = CowSlice::new();
         if self.data.len() < target_len {
             match env.fill::<T>() {
                 Ok(fill) => {
@@ -254,11 +550,12 @@ impl<T: ArrayValue> Array<T> {
                 }
             }
         } else {
+            remainder = self.data.slice(target_len..);
             self.data.truncate(target_len);
         }
         self.shape = shape;
         self.validate_shape();
-        Ok(())
+        Ok(remainder)
     }
 }
 
@@ -318,47 +615,245 @@ fn derive_shape(shape: &[usize], dims: &[isize], has_fill: bool, env: &Uiua) ->
                 Shape::from(&*shape)
             }
         }
+        // With a fill, the first and last dimensions can both be derived around a fixed middle
+        2 if has_fill && dims[0] < 0 && *dims.last().unwrap() < 0 => {
+            let middle = &dims[1..dims.len() - 1];
+            let middle_len = middle.iter().product::<isize>() as usize;
+            if middle_len == 0 {
+                return Err(env.error("Cannot reshape array with any 0 inner dimensions"));
+            }
+            // The trailing length is derived first, from the length of one of the
+            // original rows, and the leading length is then derived from the total.
+            // Both round up.
+            let row_len = shape.iter().skip(1).product::<usize>();
+            let trailing_len = derive_len(row_len, middle_len);
+            let leading_len = derive_len(shape.iter().product(), middle_len * trailing_len);
+            let mut shape = vec![leading_len];
+            shape.extend(middle.iter().map(|&dim| dim as usize));
+            shape.push(trailing_len);
+            Shape::from(&*shape)
+        }
         n => return Err(env.error(format!("Cannot reshape array with {n} negative dimensions"))),
     })
 }
 
+#[test]
+fn derive_leading_and_trailing_dims() {
+    let mut env = Uiua::with_native_sys();
+    let derive = |shape: &[usize], dims: &[isize], has_fill: bool, env: &Uiua| {
+        derive_shape(shape, dims, has_fill, env).map(|shape| shape.to_vec())
+    };
+    // Without a fill, this is still an error
+    assert!(derive(&[2, 7], &[-1, 2, -1], false, &env).is_err());
+    assert_eq!(
+        derive(&[2, 8], &[-1, 2, -1], true, &env).unwrap(),
+        [2, 2, 4]
+    );
+    // Rows that do not divide evenly round the trailing length up,
+    // and then the leading length rounds up to fit everything
+    assert_eq!(
+        derive(&[2, 7], &[-1, 2, -1], true, &env).unwrap(),
+        [2, 2, 4]
+    );
+    assert_eq!(derive(&[7], &[-1, 3, -1], true, &env).unwrap(), [3, 3, 1]);
+    assert_eq!(
+        derive(&[5, 3], &[-1, 2, -1], true, &env).unwrap(),
+        [4, 2, 2]
+    );
+    assert_eq!(
+        derive(&[3, 4], &[-1, 2, 2, -1], true, &env).unwrap(),
+        [3, 2, 2, 1]
+    );
+    assert_eq!(derive(&[3, 4], &[-1, -1], true, &env).unwrap(), [3, 4]);
+    assert!(derive(&[3, 4], &[-1, 0, -1], true, &env).is_err());
+    assert!(derive(&[3, 4], &[2, -1, -1], true, &env).is_err());
+
+    let mut arr = Array::<f64>::new(
+        tinyvec::tiny_vec![2, 7],
+        (0..14).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    env.with_fill(Value::from(0.0), |env| arr.reshape(&[-1, 2, -1], env))
+        .unwrap();
+    assert_eq!(&*arr.shape, [2, 2, 4]);
+    assert_eq!(arr.data[13..], [13.0, 0.0, 0.0]);
+    env = Uiua::with_native_sys();
+    assert!(arr.reshape(&[-1, 2, -1], &env).is_err());
+}
+
+#[test]
+fn reshape_with_remainder() {
+    let mut env = Uiua::with_native_sys();
+    let data = (0..10).map(|i| i as f64).collect::<CowSlice<_>>();
+    let mut arr = Array::<f64>::new(tinyvec::tiny_vec![10], data.clone());
+    let rest = arr.reshape_with_remainder(&[2, 3], &env).unwrap();
+    assert_eq!(&*arr.shape, [2, 3]);
+    assert_eq!(arr.data.as_slice(), [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert_eq!(&*rest.shape, [4]);
+    assert_eq!(rest.data.as_slice(), [6.0, 7.0, 8.0, 9.0]);
+    // Derived dimensions leave a remainder too
+    let mut arr = Array::<f64>::new(tinyvec::tiny_vec![10], data.clone());
+    let rest = arr.reshape_with_remainder(&[-1, 4], &env).unwrap();
+    assert_eq!(&*arr.shape, [2, 4]);
+    assert_eq!(rest.data.as_slice(), [8.0, 9.0]);
+    // Nothing is cut off when growing
+    let mut arr = Array::<f64>::new(tinyvec::tiny_vec![10], data);
+    env.with_fill(Value::from(0.0), |env| {
+        let rest = arr.reshape_with_remainder(&[3, 4], env)?;
+        assert_eq!(&*rest.shape, [0]);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(&*arr.shape, [3, 4]);
+}
+
+#[test]
+fn reshape_to_scalar() {
+    let mut env = Uiua::with_native_sys();
+    let scalar_shape = Value::from(Array::<f64>::from_iter([]));
+    let reshaped = |val: Value, env: &Uiua| -> UiuaResult<Value> {
+        let mut val = val;
+        val.reshape(&scalar_shape, env)?;
+        Ok(val)
+    };
+    // One-element arrays of every type become scalars
+    let num = reshaped(Array::from_iter([5.0]).into(), &env).unwrap();
+    assert_eq!(num, Value::from(5.0));
+    let char = reshaped(Value::from("a".to_string()), &env).unwrap();
+    assert_eq!(char, Value::from('a'));
+    let boxed = Array::from_iter([Boxed(Value::from(1.0))]);
+    let boxed = reshaped(boxed.into(), &env).unwrap();
+    assert_eq!(boxed, Array::from(Boxed(Value::from(1.0))).into());
+    assert_eq!(boxed.rank(), 0);
+    // Longer arrays are truncated like any other reshape
+    let first = reshaped(Array::from_iter([1.0, 2.0, 3.0]).into(), &env).unwrap();
+    assert_eq!(first, Value::from(1.0));
+    // Empty arrays need a fill
+    let empty = Value::from(Array::<f64>::from_iter([]));
+    assert!(reshaped(empty.clone(), &env).is_err());
+    env.with_fill(Value::from(0.0), |env| {
+        assert_eq!(reshaped(empty, env)?, Value::from(0.0));
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn reranked_shape() {
+    let env = Uiua::with_native_sys();
+    let arr = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![2, 3, 4],
+        CowSlice::from_elem(0.0, 24),
+    ));
+    for (rank, expected) in [
+        (0.0, &[24][..]),
+        (1.0, &[6, 4]),
+        (2.0, &[2, 3, 4]),
+        (4.0, &[1, 1, 2, 3, 4]),
+        (-1.0, &[2, 3, 4]),
+        (-2.0, &[6, 4]),
+        (-3.0, &[24]),
+    ] {
+        let rank = Value::from(rank);
+        let shape = arr.reranked_shape(&rank, &env).unwrap();
+        assert_eq!(&*shape, expected, "rank {rank:?}");
+        // Previewing does not change the value, but reranking does
+        assert_eq!(arr.shape(), [2, 3, 4]);
+        let mut reranked = arr.clone();
+        reranked.rerank(&rank, &env).unwrap();
+        assert_eq!(reranked.shape(), expected);
+    }
+    assert!(arr.reranked_shape(&Value::from(-4.0), &env).is_err());
+    // Ranks are not limited by the nesting depth
+    let shape = arr.reranked_shape(&Value::from(300.0), &env).unwrap();
+    assert_eq!(shape.len(), 301);
+    assert_eq!(shape[298..], [2, 3, 4]);
+    let err = arr.reranked_shape(&Value::from(1e15), &env).unwrap_err();
+    assert!(err.to_string().contains("Rerank result too large"), "{err}");
+}
+
+#[test]
+fn reshape_too_large() {
+    let mut env = Uiua::with_native_sys();
+    let too_large = |env: &mut Uiua, code: &str| {
+        let Err(err) = env.run_str(code) else {
+            panic!("{code} should have errored");
+        };
+        let err = err.to_string();
+        assert!(err.contains("result too large"), "{code}: {err}");
+    };
+    too_large(&mut env, "↯ 1e10_1e10 1");
+    too_large(&mut env, "↯ 1e18 [1 2 3]");
+    too_large(&mut env, "↯ 1e10_¯1_1e10 ⇡4");
+    let mut list = Array::<f64>::from_iter([1.0, 2.0, 3.0]);
+    assert!(list.reshape_scalar(usize::MAX, &env).is_err());
+    assert_eq!(list.shape.as_slice(), [3]);
+    list.reshape_scalar(2, &env).unwrap();
+    assert_eq!(list.shape.as_slice(), [2, 3]);
+    let mut env = Uiua::with_native_sys().with_element_limit(100);
+    assert!(env.run_str("↯ 10_10 1").is_ok());
+    too_large(&mut env, "↯ 10_11 1");
+    too_large(&mut env, "↯ 101 0");
+    too_large(&mut env, "◫ 3 ⇡50");
+    too_large(&mut env, "⊏ ↯60 0 ↯3_2 0");
+}
+
 impl Value {
     /// `rerank` this value with another
     pub fn rerank(&mut self, rank: &Self, env: &Uiua) -> UiuaResult {
-        let irank = rank.as_int(env, "Rank must be a natural number")?;
-        let shape = self.shape_mut();
+        *self.shape_mut() = self.reranked_shape(rank, env)?;
+        self.validate_shape();
+        Ok(())
+    }
+    /// Get the shape that `rerank` would give this value, without changing it
+    pub fn reranked_shape(&self, rank: &Self, env: &Uiua) -> UiuaResult<Shape> {
+        let irank = (rank.unboxed_ref()).as_int(env, "Rank must be a natural number")?;
+        let shape = self.shape();
         let rank = irank.unsigned_abs();
+        // Combine the leading axes into one
+        let combined = |split: usize| -> UiuaResult<Shape> {
+            let first_dim = (shape[..split].iter())
+                .try_fold(1usize, |acc, &dim| acc.checked_mul(dim))
+                .ok_or_else(|| {
+                    env.error(format!(
+                        "Rerank result too large: the first {split} axes \
+                        of shape {} have too many items",
+                        FormatShape(shape)
+                    ))
+                })?;
+            Ok(once(first_dim)
+                .chain(shape[split..].iter().copied())
+                .collect())
+        };
         if irank >= 0 {
             // Positive rank
             if rank >= shape.len() {
-                for _ in 0..rank - shape.len() + 1 {
-                    shape.insert(0, 1);
+                // Each added axis takes space in the shape, even with a length of 1
+                const MAX_RANK: usize = 1 << 16;
+                if rank > MAX_RANK {
+                    return Err(env.error(format!(
+                        "Rerank result too large: rank {rank} is \
+                        greater than the maximum of {MAX_RANK}"
+                    )));
                 }
+                Ok((repeat(1).take(rank - shape.len() + 1))
+                    .chain(shape.iter().copied())
+                    .collect())
             } else {
-                let mid = shape.len() - rank;
-                let new_first_dim: usize = shape[..mid].iter().product();
-                *shape = once(new_first_dim)
-                    .chain(shape[mid..].iter().copied())
-                    .collect();
+                combined(shape.len() - rank)
             }
         } else {
             // Negative rank
             if rank > shape.len() {
-                return Err(env.error(format!(
+                Err(env.error(format!(
                     "Negative rerank has magnitude {}, which is greater \
                     than the array's rank {}",
                     rank,
                     shape.len()
-                )));
+                )))
             } else {
-                let new_first_dim: usize = shape[..rank].iter().product();
-                *shape = once(new_first_dim)
-                    .chain(shape[rank..].iter().copied())
-                    .collect();
+                combined(rank)
             }
         }
-        self.validate_shape();
-        Ok(())
     }
     pub(crate) fn unrerank(&mut self, rank: &Self, orig_shape: &Self, env: &Uiua) -> UiuaResult {
         if self.rank() == 0 {
@@ -369,7 +864,7 @@ impl Value {
             }
             return Ok(());
         }
-        let irank = rank.as_int(env, "Rank must be a natural number")?;
+        let irank = (rank.unboxed_ref()).as_int(env, "Rank must be a natural number")?;
         let orig_shape = orig_shape.as_nats(env, "Shape must be a list of natural numbers")?;
         let rank = irank.unsigned_abs();
         let new_shape: Shape = if irank >= 0 {
@@ -402,6 +897,19 @@ impl Value {
     }
 }
 
+/// How `keep` handles a number of counts that differs from the number of rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountsMode {
+    /// Extend too few counts with the fill value, if there is one
+    ///
+    /// Too many counts are an error.
+    Fill,
+    /// Repeat too few counts, and ignore extra counts
+    Cycle,
+    /// Require exactly one count per row
+    Strict,
+}
+
 impl Value {
     /// Use this value as counts to `keep` another
     pub fn keep(&self, kept: Self, env: &Uiua) -> UiuaResult<Self> {
@@ -422,6 +930,14 @@ impl Value {
         } else {
             match kept {
                 Value::Num(a) => a.list_keep(&counts, env)?.into(),
+                #[cfg(all(feature = "bytes", feature = "chunked_keep"))]
+                Value::Byte(a)
+                    if a.rank() == 1
+                        && counts.len() == a.row_count()
+                        && counts.iter().all(|&n| n <= 1) =>
+                {
+                    a.chunked_mask_keep(&counts).into()
+                }
                 #[cfg(feature = "bytes")]
                 Value::Byte(a) => a.list_keep(&counts, env)?.into(),
                 Value::Complex(a) => a.list_keep(&counts, env)?.into(),
@@ -430,6 +946,19 @@ impl Value {
             }
         })
     }
+    /// Use this value as a mask to keep the rows of another where it is `0`
+    ///
+    /// This is the complement of [`Value::keep`] with a mask of `0`s and `1`s.
+    pub fn drop_where(&self, kept: Self, env: &Uiua) -> UiuaResult<Self> {
+        const MASK_ERROR: &str = "Drop-where mask must be a boolean or list of booleans";
+        let mask = self.as_nats(env, MASK_ERROR)?;
+        if mask.iter().any(|&n| n > 1) {
+            return Err(env.error(MASK_ERROR));
+        }
+        let inverted: CowSlice<f64> = mask.iter().map(|&n| (1 - n) as f64).collect();
+        let inverted = Array::new(self.shape(), inverted);
+        Value::from(inverted).keep(kept, env)
+    }
     pub(crate) fn unkeep(self, kept: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
         let counts = self.as_nats(
             env,
@@ -457,6 +986,32 @@ impl Value {
             )?,
         })
     }
+    /// `keep` the sub-arrays of another value at some depth,
+    /// using the counts at some depth of this value
+    ///
+    /// Every sub-array must keep the same number of rows,
+    /// since the results are the rows of a new array.
+    pub fn keep_depth(
+        &self,
+        kept: Self,
+        a_depth: usize,
+        b_depth: usize,
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        let counts = self.as_natural_array(
+            env,
+            "Keep amount must be a natural number \
+            or list of natural numbers",
+        )?;
+        Ok(match kept {
+            Value::Num(a) => a.keep_depth(counts, b_depth, a_depth, env)?.into(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(a) => a.keep_depth(counts, b_depth, a_depth, env)?.into(),
+            Value::Complex(a) => a.keep_depth(counts, b_depth, a_depth, env)?.into(),
+            Value::Char(a) => a.keep_depth(counts, b_depth, a_depth, env)?.into(),
+            Value::Box(a) => a.keep_depth(counts, b_depth, a_depth, env)?.into(),
+        })
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
@@ -487,62 +1042,91 @@ impl<T: ArrayValue> Array<T> {
         }
         // Keep ≥2 is a repeat
         self.shape[0] *= count;
-        let old_data = self.data.clone();
-        self.data.modify(|data| {
-            data.reserve(data.len() * count);
-            for _ in 1..count {
-                data.extend_from_slice(&old_data);
-            }
-        });
+        let len = self.data.len();
+        self.data.extend_from_within(0..len, count - 1);
         self.validate_shape();
         self
     }
     /// `keep` this array with some counts
-    pub fn list_keep(mut self, counts: &[usize], env: &Uiua) -> UiuaResult<Self> {
+    ///
+    /// If there are fewer counts than rows, they are extended with the fill value.
+    pub fn list_keep(self, counts: &[usize], env: &Uiua) -> UiuaResult<Self> {
+        self.list_keep_with_mode(counts, CountsMode::Fill, env)
+    }
+    /// `keep` this array with some counts, choosing how to handle
+    /// a number of counts that differs from the number of rows
+    pub fn list_keep_with_mode(
+        mut self,
+        counts: &[usize],
+        mode: CountsMode,
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
         let mut amount = Cow::Borrowed(counts);
-        match amount.len().cmp(&self.row_count()) {
-            Ordering::Equal => {}
-            Ordering::Less => match env.fill::<f64>() {
-                Ok(fill) => {
-                    if fill < 0.0 || fill.fract() != 0.0 {
+        let row_count = self.row_count();
+        match mode {
+            CountsMode::Fill => match amount.len().cmp(&self.row_count()) {
+                Ordering::Equal => {}
+                Ordering::Less => match env.fill::<f64>() {
+                    Ok(fill) => {
+                        if fill < 0.0 || fill.fract() != 0.0 {
+                            return Err(env.error(format!(
+                                "Fill value for keep must be a non-negative\
+                                integer, but it is {fill}"
+                            )));
+                        }
+                        let fill = fill as usize;
+                        let mut new_amount = amount.to_vec();
+                        new_amount.extend(repeat(fill).take(self.row_count() - amount.len()));
+                        amount = new_amount.into();
+                    }
+                    Err(e) => {
                         return Err(env.error(format!(
-                            "Fill value for keep must be a non-negative\
-                            integer, but it is {fill}"
+                            "Cannot keep array with shape {} with array of shape {}{e}",
+                            self.format_shape(),
+                            FormatShape(&[amount.len()])
                         )));
                     }
-                    let fill = fill as usize;
-                    let mut new_amount = amount.to_vec();
-                    new_amount.extend(repeat(fill).take(self.row_count() - amount.len()));
-                    amount = new_amount.into();
+                },
+                Ordering::Greater => {
+                    return Err(env.error(match env.fill::<f64>() {
+                        Ok(_) => {
+                            format!(
+                                "Cannot keep array with shape {} with array of shape {}.\
+                                A fill value is available, but keep can only been filled\
+                                if there are fewer counts than rows.",
+                                self.format_shape(),
+                                FormatShape(amount.as_ref())
+                            )
+                        }
+                        Err(e) => {
+                            format!(
+                                "Cannot keep array with shape {} with array of shape {}{e}",
+                                self.format_shape(),
+                                FormatShape(amount.as_ref())
+                            )
+                        }
+                    }))
                 }
-                Err(e) => {
+            },
+            CountsMode::Cycle if amount.len() != row_count => {
+                if amount.is_empty() {
                     return Err(env.error(format!(
-                        "Cannot keep array with shape {} with array of shape {}{e}",
-                        self.format_shape(),
-                        FormatShape(&[amount.len()])
+                        "Cannot keep array with shape {} by cycling no counts",
+                        self.format_shape()
                     )));
                 }
-            },
-            Ordering::Greater => {
-                return Err(env.error(match env.fill::<f64>() {
-                    Ok(_) => {
-                        format!(
-                            "Cannot keep array with shape {} with array of shape {}.\
-                            A fill value is available, but keep can only been filled\
-                            if there are fewer counts than rows.",
-                            self.format_shape(),
-                            FormatShape(amount.as_ref())
-                        )
-                    }
-                    Err(e) => {
-                        format!(
-                            "Cannot keep array with shape {} with array of shape {}{e}",
-                            self.format_shape(),
-                            FormatShape(amount.as_ref())
-                        )
-                    }
-                }))
+                amount = (amount.iter().cycle().take(row_count).copied())
+                    .collect::<Vec<_>>()
+                    .into();
+            }
+            CountsMode::Strict if amount.len() != row_count => {
+                return Err(env.error(format!(
+                    "Cannot keep array with shape {} with array of shape {}",
+                    self.format_shape(),
+                    FormatShape(&[amount.len()])
+                )));
             }
+            CountsMode::Cycle | CountsMode::Strict => {}
         }
         if self.rank() == 0 {
             if amount.len() != 1 {
@@ -567,38 +1151,83 @@ impl<T: ArrayValue> Array<T> {
                 }
             }
             let row_len = self.row_len();
-            if all_bools {
-                let new_flat_len = true_count * row_len;
-                let mut new_data = CowSlice::with_capacity(new_flat_len);
-                if row_len > 0 {
-                    for (b, r) in amount.iter().zip(self.data.chunks_exact(row_len)) {
-                        if *b == 1 {
-                            new_data.extend_from_slice(r);
-                        }
-                    }
-                }
-                self.data = new_data;
-                self.shape[0] = true_count;
+            let new_len = if all_bools {
+                let mut i = 0;
+                self.data.retain(|_| {
+                    let keep = amount[i / row_len] == 1;
+                    i += 1;
+                    keep
+                });
+                true_count
             } else {
                 let mut new_data = CowSlice::new();
-                let mut new_len = 0;
                 if row_len > 0 {
                     for (n, r) in amount.iter().zip(self.data.chunks_exact(row_len)) {
-                        new_len += *n;
                         for _ in 0..*n {
                             new_data.extend_from_slice(r);
                         }
                     }
-                } else {
-                    new_len = amount.iter().sum();
                 }
                 self.data = new_data;
-                self.shape[0] = new_len;
-            }
+                amount.iter().sum()
+            };
+            // Only the row count changes, so keeping nothing
+            // still leaves rows of the same shape to join with
+            self.shape[0] = new_len;
         }
         self.validate_shape();
         Ok(self)
     }
+    /// `keep` the sub-arrays of this array at some depth
+    ///
+    /// Each sub-array is kept with the aligned counts.
+    /// Every sub-array must end up with the same shape.
+    pub fn keep_depth(
+        mut self,
+        counts: Array<usize>,
+        depth: usize,
+        counts_depth: usize,
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        let kept_rows: RefCell<Vec<Self>> = RefCell::new(Vec::new());
+        let sub_rank = Cell::new(0);
+        self.depth_slices(&counts, depth, counts_depth, env, |ash, a, bsh, b, env| {
+            sub_rank.set(ash.len());
+            let sub = Array::new(Shape::from(ash), a.iter().cloned().collect::<CowSlice<_>>());
+            let kept = match bsh.len() {
+                0 => sub.scalar_keep(b[0]),
+                1 => sub.list_keep(b, env)?,
+                rank => return Err(env.error(format!("Cannot keep by rank {rank} array"))),
+            };
+            let mut kept_rows = kept_rows.borrow_mut();
+            if let Some(first) = kept_rows.first() {
+                if first.shape != kept.shape {
+                    return Err(env.error(format!(
+                        "Cannot keep at depth because sub-arrays were kept \
+                        to different shapes {} and {}",
+                        first.format_shape(),
+                        kept.format_shape()
+                    )));
+                }
+            }
+            kept_rows.push(kept);
+            Ok(())
+        })?;
+        let kept_rows = kept_rows.into_inner();
+        let Some(first) = kept_rows.first() else {
+            return Ok(self);
+        };
+        // Replace the sub-arrays' part of the shape with the kept shape
+        let mut shape = Shape::from(&self.shape[..self.rank() - sub_rank.get()]);
+        shape.extend_from_slice(&first.shape);
+        let mut data = CowSlice::with_capacity(first.element_count() * kept_rows.len());
+        for row in &kept_rows {
+            data.extend_from_slice(&row.data);
+        }
+        let arr = Array::new(shape, data);
+        arr.validate_shape();
+        Ok(arr)
+    }
     pub(crate) fn unkeep(self, counts: &[usize], into: Self, env: &Uiua) -> UiuaResult<Self> {
         if counts.iter().any(|&n| n > 1) {
             return Err(env.error("Cannot invert keep with non-boolean counts"));
@@ -630,23 +1259,242 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+#[test]
+fn keep_counts_modes() {
+    let mut env = Uiua::with_native_sys();
+    let list = Array::<f64>::from_iter((1..=5).map(|i| i as f64));
+    let keep = |counts: &[usize], mode: CountsMode, env: &Uiua| {
+        (list.clone().list_keep_with_mode(counts, mode, env)).map(|kept| kept.data.to_vec())
+    };
+    // Cycling repeats a short pattern and truncates a long one
+    let kept = keep(&[1, 0], CountsMode::Cycle, &env).unwrap();
+    assert_eq!(kept, [1.0, 3.0, 5.0]);
+    let kept = keep(&[0, 2, 0, 0, 1, 1, 1], CountsMode::Cycle, &env).unwrap();
+    assert_eq!(kept, [2.0, 2.0, 5.0]);
+    assert!(keep(&[], CountsMode::Cycle, &env).is_err());
+    // Only filling uses the fill value
+    assert!(keep(&[1, 0], CountsMode::Fill, &env).is_err());
+    env.with_fill(Value::from(1.0), |env| {
+        assert_eq!(
+            keep(&[0, 2], CountsMode::Fill, env)?,
+            [2.0, 2.0, 3.0, 4.0, 5.0]
+        );
+        assert_eq!(keep(&[0, 2], CountsMode::Cycle, env)?, [2.0, 2.0, 4.0, 4.0]);
+        assert!(keep(&[0, 2], CountsMode::Strict, env).is_err());
+        Ok(())
+    })
+    .unwrap();
+    let counts = [1, 1, 0, 0, 2];
+    let kept = keep(&counts, CountsMode::Strict, &env).unwrap();
+    assert_eq!(kept, keep(&counts, CountsMode::Cycle, &env).unwrap());
+    assert_eq!(kept, [1.0, 2.0, 5.0, 5.0]);
+}
+
+#[test]
+fn drop_where() {
+    let env = Uiua::with_native_sys();
+    let list = |items: &[f64]| Value::from(Array::from_iter(items.iter().copied()));
+    let kept = list(&[1.0, 2.0, 3.0, 4.0]);
+    let mask = list(&[1.0, 0.0, 0.0, 1.0]);
+    let dropped = mask.drop_where(kept.clone(), &env).unwrap();
+    assert_eq!(dropped, list(&[2.0, 3.0]));
+    // Dropping and keeping with the same mask partition the rows
+    let masked = mask.keep(kept.clone(), &env).unwrap();
+    assert_eq!(masked, list(&[1.0, 4.0]));
+    // A scalar mask drops everything or nothing
+    let none = Value::from(1.0).drop_where(kept.clone(), &env).unwrap();
+    assert_eq!(none.row_count(), 0);
+    let all = Value::from(0.0).drop_where(kept.clone(), &env).unwrap();
+    assert_eq!(all, kept);
+    // Masks must be boolean
+    assert!(list(&[2.0, 0.0, 0.0, 1.0]).drop_where(kept, &env).is_err());
+}
+
+#[test]
+fn keep_depth() {
+    let env = Uiua::with_native_sys();
+    let table = |shape: [usize; 2], data: &[f64]| {
+        Value::from(Array::<f64>::new(
+            tinyvec::tiny_vec![shape[0], shape[1]],
+            data.iter().copied().collect::<CowSlice<_>>(),
+        ))
+    };
+    let kept = table([2, 3], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    // Each row kept with its own counts
+    let counts = table([2, 3], &[1.0, 0.0, 1.0, 0.0, 2.0, 0.0]);
+    let res = counts.keep_depth(kept.clone(), 1, 1, &env).unwrap();
+    assert_eq!(res, table([2, 2], &[1.0, 3.0, 5.0, 5.0]));
+    // The same counts for every row
+    let counts = Value::from_iter([0.0, 1.0, 1.0]);
+    let res = counts.keep_depth(kept.clone(), 0, 1, &env).unwrap();
+    assert_eq!(res, table([2, 2], &[2.0, 3.0, 5.0, 6.0]));
+    // Rows that keep different amounts can't be combined
+    let counts = table([2, 3], &[1.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+    let err = counts.keep_depth(kept, 1, 1, &env).unwrap_err();
+    assert!(err.to_string().contains("different shapes"), "{err}");
+}
+
+#[test]
+fn keep_nothing_keeps_row_shape() {
+    let mut env = Uiua::with_native_sys();
+    let shaped = |shape: &[usize]| {
+        let data = (0..shape.iter().product()).map(|i| i as f64);
+        Array::<f64>::new(Shape::from(shape), data.collect::<CowSlice<_>>())
+    };
+    let table = shaped(&[3, 4]);
+    // Scalar, boolean, and non-boolean counts
+    assert_eq!(table.clone().scalar_keep(0).shape(), &[0, 4]);
+    let kept = table.clone().list_keep(&[0, 0, 0], &env).unwrap();
+    assert_eq!(kept.shape(), &[0, 4]);
+    let kept = (table.clone())
+        .list_keep_with_mode(&[0], CountsMode::Cycle, &env)
+        .unwrap();
+    assert_eq!(kept.shape(), &[0, 4]);
+    env.with_fill(Value::from(0.0), |env| {
+        assert_eq!(table.clone().list_keep(&[], env)?.shape(), &[0, 4]);
+        Ok(())
+    })
+    .unwrap();
+    // The result can still be joined with rows of the same shape
+    let kept = Value::from(table.clone().list_keep(&[0, 0, 0], &env).unwrap());
+    let joined = kept.join(Value::from(shaped(&[2, 4])), &env).unwrap();
+    assert_eq!(joined.shape(), &[2, 4]);
+    // Rows with no elements keep their shape too
+    let empty_rows = shaped(&[3, 0, 2]);
+    assert_eq!(empty_rows.clone().scalar_keep(0).shape(), &[0, 0, 2]);
+    let kept = empty_rows.clone().list_keep(&[0, 0, 0], &env).unwrap();
+    assert_eq!(kept.shape(), &[0, 0, 2]);
+    let kept = empty_rows.list_keep(&[2, 0, 1], &env).unwrap();
+    assert_eq!(kept.shape(), &[3, 0, 2]);
+}
+
+#[cfg(all(feature = "bytes", feature = "chunked_keep"))]
+impl Array<u8> {
+    /// `keep` this rank 1 array with a boolean mask
+    ///
+    /// The mask must be the same length as the array
+    fn chunked_mask_keep(mut self, mask: &[usize]) -> Self {
+        let mut data = EcoVec::from_elem(0, self.data.len());
+        let kept = compact_bytes_chunked(mask, &self.data, data.make_mut());
+        data.truncate(kept);
+        self.shape[0] = kept;
+        self.data = data.into();
+        self
+    }
+}
+
+/// Pack the bytes whose mask value is `1` into the front of `dst`,
+/// returning the number of bytes kept
+///
+/// The mask is processed in chunks of 8 so that fully kept or fully
+/// dropped chunks are handled in bulk and mixed chunks are compacted
+/// without branching on each byte.
+#[cfg(all(feature = "bytes", feature = "chunked_keep"))]
+fn compact_bytes_chunked(mask: &[usize], src: &[u8], dst: &mut [u8]) -> usize {
+    debug_assert_eq!(mask.len(), src.len());
+    debug_assert!(dst.len() >= src.len());
+    let mut j = 0;
+    for (mask, src) in mask.chunks(8).zip(src.chunks(8)) {
+        let bits = (mask.iter().enumerate()).fold(0u8, |bits, (k, &m)| bits | (m as u8) << k);
+        if bits == 0 {
+            continue;
+        }
+        if src.len() == 8 && bits == u8::MAX {
+            dst[j..j + 8].copy_from_slice(src);
+            j += 8;
+            continue;
+        }
+        // `j` never passes the index of the current byte,
+        // so every write is in bounds
+        for (k, &b) in src.iter().enumerate() {
+            dst[j] = b;
+            j += (bits >> k & 1) as usize;
+        }
+    }
+    j
+}
+
+#[cfg(all(feature = "bytes", feature = "chunked_keep"))]
+#[test]
+fn compact_bytes_matches_scalar() {
+    use rand::prelude::*;
+    let mut rng = SmallRng::seed_from_u64(0);
+    for _ in 0..200 {
+        let len = rng.gen_range(0..100);
+        let src: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        let mask: Vec<usize> = (0..len).map(|_| rng.gen_range(0..=1)).collect();
+        let expected: Vec<u8> = (src.iter().zip(&mask))
+            .filter(|(_, &m)| m == 1)
+            .map(|(&b, _)| b)
+            .collect();
+        let mut dst = vec![0; len];
+        let kept = compact_bytes_chunked(&mask, &src, &mut dst);
+        assert_eq!(&dst[..kept], expected);
+    }
+}
+
+/// How `rotate` treats the rows that wrap around
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RotateMode {
+    /// Always wrap rows around, ignoring any fill value
+    Cyclic,
+    /// Replace wrapped rows with the fill value if one is set,
+    /// and wrap them around otherwise
+    ///
+    /// This is what [`Value::rotate`] does.
+    #[default]
+    ShiftFill,
+    /// Always replace wrapped rows with zeros, without needing a fill value
+    ///
+    /// Characters are replaced with spaces and boxes with empty lists.
+    ShiftZero,
+}
+
+impl RotateMode {
+    /// The value to shift in, if any
+    fn shift_value<T: ArrayValue>(self, env: &Uiua) -> Option<T> {
+        match self {
+            RotateMode::Cyclic => None,
+            RotateMode::ShiftFill => env.fill::<T>().ok(),
+            RotateMode::ShiftZero => Some(T::proxy()),
+        }
+    }
+}
+
 impl Value {
     /// Use this value to `rotate` another
-    pub fn rotate(&self, mut rotated: Self, env: &Uiua) -> UiuaResult<Self> {
-        let by = self.as_ints(env, "Rotation amount must be a list of integers")?;
+    ///
+    /// If a fill value is set, the rows that wrap around are replaced
+    /// with the fill value, turning the rotation into a shift.
+    /// Use [`Value::rotate_cyclic`] to rotate regardless of the fill.
+    pub fn rotate(&self, rotated: Self, env: &Uiua) -> UiuaResult<Self> {
+        self.rotate_with_mode(rotated, RotateMode::ShiftFill, env)
+    }
+    /// Use this value to `rotate` another, wrapping rows around even if a fill value is set
+    pub fn rotate_cyclic(&self, rotated: Self, env: &Uiua) -> UiuaResult<Self> {
+        self.rotate_with_mode(rotated, RotateMode::Cyclic, env)
+    }
+    /// Use this value to `rotate` another, treating wrapped rows according to `mode`
+    pub fn rotate_with_mode(
+        &self,
+        mut rotated: Self,
+        mode: RotateMode,
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        let by = (self.unboxed_ref()).as_ints(env, "Rotation amount must be a list of integers")?;
         #[cfg(feature = "bytes")]
-        if env.fill::<f64>().is_ok() {
+        if mode == RotateMode::ShiftFill && env.fill::<f64>().is_ok() {
             if let Value::Byte(bytes) = &rotated {
                 rotated = bytes.convert_ref::<f64>().into();
             }
         }
         match &mut rotated {
-            Value::Num(a) => a.rotate(&by, env)?,
+            Value::Num(a) => a.rotate_with_mode(&by, mode, env)?,
             #[cfg(feature = "bytes")]
-            Value::Byte(a) => a.rotate(&by, env)?,
-            Value::Complex(a) => a.rotate(&by, env)?,
-            Value::Char(a) => a.rotate(&by, env)?,
-            Value::Box(a) => a.rotate(&by, env)?,
+            Value::Byte(a) => a.rotate_bytes(&by, mode, env)?,
+            Value::Complex(a) => a.rotate_with_mode(&by, mode, env)?,
+            Value::Char(a) => a.rotate_with_mode(&by, mode, env)?,
+            Value::Box(a) => a.rotate_with_mode(&by, mode, env)?,
         }
         Ok(rotated)
     }
@@ -664,6 +1512,19 @@ impl Value {
                 rotated = bytes.convert_ref::<f64>().into();
             }
         }
+        // Every row is rotated by the same amount, so there is nothing to pair up
+        if let Some(amount) = uniform_rotation(&by, a_depth, rotated.shape(), b_depth) {
+            let depth = b_depth.min(rotated.rank());
+            match &mut rotated {
+                Value::Num(a) => a.rotate_depth_uniform(amount, depth),
+                #[cfg(feature = "bytes")]
+                Value::Byte(a) => a.rotate_depth_uniform(amount, depth),
+                Value::Complex(a) => a.rotate_depth_uniform(amount, depth),
+                Value::Char(a) => a.rotate_depth_uniform(amount, depth),
+                Value::Box(a) => a.rotate_depth_uniform(amount, depth),
+            }
+            return Ok(rotated);
+        }
         match &mut rotated {
             Value::Num(a) => a.rotate_depth(by, b_depth, a_depth, env)?,
             #[cfg(feature = "bytes")]
@@ -676,9 +1537,69 @@ impl Value {
     }
 }
 
-impl<T: ArrayValue> Array<T> {
-    /// `rotate` this array by the given amount
+/// Get the amount to rotate by if a depth rotation would rotate every row by the same amount
+///
+/// This is the case when `by` is a scalar, or when it is made of scalar rows
+/// that are all the same and pair one-to-one with the rotated array's rows.
+fn uniform_rotation(
+    by: &Array<isize>,
+    by_depth: usize,
+    rotated_shape: &[usize],
+    rotated_depth: usize,
+) -> Option<isize> {
+    let by_depth = by_depth.min(by.rank());
+    let rotated_depth = rotated_depth.min(rotated_shape.len());
+    if by.rank() != by_depth
+        || by_depth > 0 && (by_depth != rotated_depth || by.shape[..] != rotated_shape[..by_depth])
+    {
+        return None;
+    }
+    let (&first, rest) = by.data.split_first()?;
+    rest.iter().all(|&n| n == first).then_some(first)
+}
+
+impl<T: ArrayValue> Array<T> {
+    /// `rotate` this array by the given amount
+    ///
+    /// If a fill value is set, the rows that wrap around are replaced with it
     pub fn rotate(&mut self, by: &[isize], env: &Uiua) -> UiuaResult {
+        self.rotate_with_mode(by, RotateMode::ShiftFill, env)
+    }
+    /// `rotate` this array by the given amount, ignoring any fill value
+    pub fn rotate_cyclic(&mut self, by: &[isize], env: &Uiua) -> UiuaResult {
+        self.rotate_with_mode(by, RotateMode::Cyclic, env)
+    }
+    /// `rotate` this array's rows cyclically, returning the rows that wrapped around
+    ///
+    /// The returned rows are in the order they appear after the rotation.
+    /// Only the first axis is supported, and any fill value is ignored.
+    pub fn rotate_capture(&mut self, by: isize, env: &Uiua) -> UiuaResult<Self> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot rotate a scalar"));
+        }
+        let row_count = self.row_count();
+        let mut wrapped_shape = self.shape.clone();
+        wrapped_shape[0] = 0;
+        if row_count == 0 {
+            return Ok(Array::new(wrapped_shape, CowSlice::new()));
+        }
+        let mid = by.rem_euclid(row_count as isize) as usize;
+        let row_len = self.row_len();
+        // Rotating forward wraps the leading rows, and backward the trailing ones
+        let wrapped_rows = if by >= 0 {
+            0..mid
+        } else if mid == 0 {
+            0..0
+        } else {
+            mid..row_count
+        };
+        wrapped_shape[0] = wrapped_rows.len();
+        let wrapped = (self.data).slice(wrapped_rows.start * row_len..wrapped_rows.end * row_len);
+        rotate(&[by], &self.shape, self.data.as_mut_slice());
+        Ok(Array::new(wrapped_shape, wrapped))
+    }
+    /// `rotate` this array by the given amount, treating wrapped rows according to `mode`
+    pub fn rotate_with_mode(&mut self, by: &[isize], mode: RotateMode, env: &Uiua) -> UiuaResult {
         if by.len() > self.rank() {
             return Err(env.error(format!(
                 "Cannot rotate rank {} array with index of length {}",
@@ -688,11 +1609,22 @@ impl<T: ArrayValue> Array<T> {
         }
         let data = self.data.as_mut_slice();
         rotate(by, &self.shape, data);
-        if let Ok(fill) = env.fill::<T>() {
+        if let Some(fill) = mode.shift_value::<T>(env) {
             fill_shift(by, &self.shape, data, fill);
         }
         Ok(())
     }
+    /// Rotate every row at the given depth by the same amount
+    fn rotate_depth_uniform(&mut self, by: isize, depth: usize) {
+        let row_shape = &self.shape[depth..];
+        let row_len: usize = row_shape.iter().product();
+        if row_len == 0 {
+            return;
+        }
+        for row in self.data.as_mut_slice().chunks_exact_mut(row_len) {
+            rotate(&[by], row_shape, row);
+        }
+    }
     pub(crate) fn rotate_depth(
         &mut self,
         by: Array<isize>,
@@ -710,17 +1642,237 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+impl Array<f64> {
+    /// `rotate` this array by fractional amounts, linearly interpolating
+    /// between the neighboring integer rotations
+    ///
+    /// Each amount is split into its floor `n` and a fraction `f`, and the
+    /// result along that axis is the rotation by `n` blended with the
+    /// rotation by `n + 1`, weighted by `f`. Whole amounts give the same
+    /// result as [`Array::rotate_with_mode`] with [`RotateMode::Cyclic`].
+    ///
+    /// Rows always wrap around and fill values are not used, so the last
+    /// row along an axis is blended with the first.
+    pub fn rotate_fractional(&mut self, by: &[f64], env: &Uiua) -> UiuaResult {
+        if by.len() > self.rank() {
+            return Err(env.error(format!(
+                "Cannot rotate rank {} array with index of length {}",
+                self.rank(),
+                by.len()
+            )));
+        }
+        if let Some(n) = by.iter().find(|n| !n.is_finite()) {
+            return Err(env.error(format!("Cannot rotate by {n}")));
+        }
+        if self.data.is_empty() {
+            return Ok(());
+        }
+        // The blend along each axis is linear, so the axes are rotated one at a time
+        for (axis, &n) in by.iter().enumerate() {
+            let whole = n.floor();
+            let frac = n - whole;
+            let mut amounts = vec![0; axis + 1];
+            amounts[axis] = whole.rem_euclid(self.shape[axis] as f64) as isize;
+            rotate(&amounts, &self.shape, self.data.as_mut_slice());
+            if frac == 0.0 {
+                continue;
+            }
+            let mut next = self.data.clone();
+            amounts[axis] = 1;
+            rotate(&amounts, &self.shape, next.as_mut_slice());
+            for (a, b) in self.data.as_mut_slice().iter_mut().zip(&next) {
+                *a += (b - *a) * frac;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Array<u8> {
+    /// `rotate` this byte array by the given amount
+    ///
+    /// This uses the standard library's slice rotation,
+    /// which is much faster for large byte buffers like images
+    fn rotate_bytes(&mut self, by: &[isize], mode: RotateMode, env: &Uiua) -> UiuaResult {
+        if by.len() > self.rank() {
+            return Err(env.error(format!(
+                "Cannot rotate rank {} array with index of length {}",
+                self.rank(),
+                by.len()
+            )));
+        }
+        let data = self.data.as_mut_slice();
+        rotate_slices(by, &self.shape, data);
+        if let Some(fill) = mode.shift_value::<u8>(env) {
+            fill_shift(by, &self.shape, data, fill);
+        }
+        Ok(())
+    }
+}
+
+/// `rotate` data in place by rotating each contiguous slice of rows
+#[cfg(feature = "bytes")]
+fn rotate_slices<T: Copy>(by: &[isize], shape: &[usize], data: &mut [T]) {
+    if by.is_empty() || shape.is_empty() {
+        return;
+    }
+    let row_count = shape[0];
+    // An empty array may still have rows, but they have no items to move
+    if data.is_empty() {
+        return;
+    }
+    let row_len: usize = shape[1..].iter().product();
+    let offset = by[0];
+    let mid = offset.rem_euclid(row_count as isize) as usize;
+    data.rotate_left(mid * row_len);
+    let index = &by[1..];
+    let shape = &shape[1..];
+    if index.is_empty() || shape.is_empty() {
+        return;
+    }
+    for cell in data.chunks_mut(row_len) {
+        rotate_slices(index, shape, cell);
+    }
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn rotate_slices_matches_rotate() {
+    use rand::prelude::*;
+    let mut rng = SmallRng::seed_from_u64(0);
+    for _ in 0..200 {
+        let rank = rng.gen_range(1..=3);
+        let shape: Vec<usize> = (0..rank).map(|_| rng.gen_range(1..6)).collect();
+        let by: Vec<isize> = (0..rng.gen_range(1..=rank))
+            .map(|_| rng.gen_range(-8..=8))
+            .collect();
+        let data: Vec<u8> = (0..shape.iter().product()).map(|_| rng.gen()).collect();
+        let mut expected = data.clone();
+        rotate(&by, &shape, &mut expected);
+        let mut fast = data;
+        rotate_slices(&by, &shape, &mut fast);
+        assert_eq!(fast, expected, "shape: {shape:?}, by: {by:?}");
+    }
+}
+
+#[test]
+fn rotate_capture() {
+    let env = Uiua::with_native_sys();
+    let list = |items: &[f64]| Array::<f64>::from_iter(items.iter().copied());
+    let check = |by: isize, rotated: &[f64], wrapped: &[f64]| {
+        let mut arr = list(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        let captured = arr.rotate_capture(by, &env).unwrap();
+        assert_eq!(arr.data.as_slice(), rotated, "{by}");
+        assert_eq!(captured.data.as_slice(), wrapped, "{by}");
+        assert_eq!(captured.row_count(), wrapped.len(), "{by}");
+    };
+    check(2, &[3.0, 4.0, 5.0, 1.0, 2.0], &[1.0, 2.0]);
+    check(-2, &[4.0, 5.0, 1.0, 2.0, 3.0], &[4.0, 5.0]);
+    check(7, &[3.0, 4.0, 5.0, 1.0, 2.0], &[1.0, 2.0]);
+    check(-5, &[1.0, 2.0, 3.0, 4.0, 5.0], &[]);
+    check(0, &[1.0, 2.0, 3.0, 4.0, 5.0], &[]);
+
+    // Whole rows are captured
+    let mut table = Array::<f64>::new(
+        tinyvec::tiny_vec![3, 2],
+        (0..6).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    let captured = table.rotate_capture(-1, &env).unwrap();
+    assert_eq!(&*captured.shape, [1, 2]);
+    assert_eq!(captured.data.as_slice(), [4.0, 5.0]);
+    assert_eq!(table.data.as_slice(), [4.0, 5.0, 0.0, 1.0, 2.0, 3.0]);
+    assert!(Array::from(1.0).rotate_capture(1, &env).is_err());
+}
+
+#[test]
+fn rotate_modes() {
+    let mut env = Uiua::with_native_sys();
+    let by = Value::from(1.0);
+    let list = |items: &[f64]| Value::from(Array::from_iter(items.iter().copied()));
+    let rotate = |mode, env: &Uiua| {
+        by.rotate_with_mode(list(&[1.0, 2.0, 3.0]), mode, env)
+            .unwrap()
+    };
+    // Without a fill, only shifting in zeros differs from cycling
+    assert_eq!(rotate(RotateMode::Cyclic, &env), list(&[2.0, 3.0, 1.0]));
+    assert_eq!(rotate(RotateMode::ShiftFill, &env), list(&[2.0, 3.0, 1.0]));
+    assert_eq!(rotate(RotateMode::ShiftZero, &env), list(&[2.0, 3.0, 0.0]));
+    // With a fill, only cycling ignores it
+    env.with_fill(Value::from(9.0), |env| {
+        assert_eq!(rotate(RotateMode::Cyclic, env), list(&[2.0, 3.0, 1.0]));
+        assert_eq!(rotate(RotateMode::ShiftFill, env), list(&[2.0, 3.0, 9.0]));
+        assert_eq!(rotate(RotateMode::ShiftZero, env), list(&[2.0, 3.0, 0.0]));
+        assert_eq!(
+            by.rotate_cyclic(list(&[1.0, 2.0, 3.0]), env)?,
+            list(&[2.0, 3.0, 1.0])
+        );
+        #[cfg(feature = "bytes")]
+        {
+            let bytes = Value::from(Array::<u8>::from_iter([1, 2, 3]));
+            let rotated = by.rotate_cyclic(bytes, env)?;
+            assert_eq!(rotated, Array::<u8>::from_iter([2, 3, 1]).into());
+        }
+        Ok(())
+    })
+    .unwrap();
+    // Characters are shifted with spaces
+    let chars =
+        (by.rotate_with_mode(Value::from("abc".to_string()), RotateMode::ShiftZero, &env)).unwrap();
+    assert_eq!(chars, Value::from("bc ".to_string()));
+}
+
+#[test]
+fn rotate_fractional() {
+    let env = Uiua::with_native_sys();
+    let rotated = |by: &[f64], shape: &[usize], items: &[f64]| {
+        let mut arr = Array::new(
+            Shape::from(shape),
+            items.iter().copied().collect::<CowSlice<_>>(),
+        );
+        arr.rotate_fractional(by, &env).map(|_| arr.data.to_vec())
+    };
+    let list = [1.0, 2.0, 3.0, 4.0];
+    // Halfway between [1 2 3 4] and [2 3 4 1]
+    assert_eq!(rotated(&[0.5], &[4], &list).unwrap(), [1.5, 2.5, 3.5, 2.5]);
+    // A quarter of the way from [2 3 4 1] to [3 4 1 2]
+    assert_eq!(
+        rotated(&[1.25], &[4], &list).unwrap(),
+        [2.25, 3.25, 3.25, 1.25]
+    );
+    // Halfway between [4 1 2 3] and [1 2 3 4]
+    assert_eq!(rotated(&[-0.5], &[4], &list).unwrap(), [2.5, 1.5, 2.5, 3.5]);
+    // Whole amounts are ordinary rotations
+    assert_eq!(rotated(&[2.0], &[4], &list).unwrap(), [3.0, 4.0, 1.0, 2.0]);
+    assert_eq!(rotated(&[-9.0], &[4], &list).unwrap(), [4.0, 1.0, 2.0, 3.0]);
+    // Each axis is blended
+    let table = [1.0, 2.0, 3.0, 4.0];
+    assert_eq!(
+        rotated(&[0.5], &[2, 2], &table).unwrap(),
+        [2.0, 3.0, 2.0, 3.0]
+    );
+    assert_eq!(
+        rotated(&[0.0, 0.5], &[2, 2], &table).unwrap(),
+        [1.5, 1.5, 3.5, 3.5]
+    );
+    assert_eq!(rotated(&[0.5, 0.5], &[2, 2], &table).unwrap(), [2.5; 4]);
+    assert_eq!(rotated(&[0.5], &[0, 2], &[]).unwrap(), Vec::<f64>::new());
+    assert!(rotated(&[0.5, 0.5], &[4], &list).is_err());
+    assert!(rotated(&[f64::NAN], &[4], &list).is_err());
+}
+
 fn rotate<T>(by: &[isize], shape: &[usize], data: &mut [T]) {
     if by.is_empty() || shape.is_empty() {
         return;
     }
     let row_count = shape[0];
-    if row_count == 0 {
+    // An empty array may still have rows, but they have no items to move
+    if data.is_empty() {
         return;
     }
     let row_len = shape[1..].iter().product();
     let offset = by[0];
-    let mid = (row_count as isize + offset).rem_euclid(row_count as isize) as usize;
+    let mid = offset.rem_euclid(row_count as isize) as usize;
     let (left, right) = data.split_at_mut(mid * row_len);
     left.reverse();
     right.reverse();
@@ -739,14 +1891,14 @@ fn fill_shift<T: Clone>(by: &[isize], shape: &[usize], data: &mut [T], fill: T)
     if by.is_empty() || shape.is_empty() {
         return;
     }
-    let row_count = shape[0];
-    if row_count == 0 {
+    // An empty array may still have rows, but they have no items to move
+    if data.is_empty() {
         return;
     }
     let offset = by[0];
     let row_len: usize = shape[1..].iter().product();
     if offset != 0 {
-        let abs_offset = offset.unsigned_abs() * row_len;
+        let abs_offset = offset.unsigned_abs().saturating_mul(row_len);
         let data_len = data.len();
         if offset > 0 {
             for val in &mut data[data_len.saturating_sub(abs_offset)..] {
@@ -768,24 +1920,112 @@ fn fill_shift<T: Clone>(by: &[isize], shape: &[usize], data: &mut [T], fill: T)
     }
 }
 
+#[test]
+fn fill_shift_matches_reference() {
+    // Shift by computing the source coordinates of each item directly
+    fn reference(by: &[isize], shape: &[usize], data: &[i32], fill: i32) -> Vec<i32> {
+        let mut shifted = Vec::with_capacity(data.len());
+        let mut index = vec![0; shape.len()];
+        for i in 0..data.len() {
+            data_index_to_shape_index(i, shape, &mut index);
+            let mut src = 0;
+            let mut in_bounds = true;
+            for (axis, (&i, &dim)) in index.iter().zip(shape).enumerate() {
+                let j = (i as isize).checked_add(by.get(axis).copied().unwrap_or(0));
+                let j = j.filter(|j| (0..dim as isize).contains(j));
+                in_bounds &= j.is_some();
+                src = src * dim + j.unwrap_or(0) as usize;
+            }
+            shifted.push(if in_bounds { data[src] } else { fill });
+        }
+        shifted
+    }
+    let check = |by: &[isize], shape: &[usize]| {
+        let data: Vec<i32> = (1..=shape.iter().product::<usize>() as i32).collect();
+        let mut shifted = data.clone();
+        rotate(by, shape, &mut shifted);
+        fill_shift(by, shape, &mut shifted, 0);
+        assert_eq!(
+            shifted,
+            reference(by, shape, &data, 0),
+            "shape: {shape:?}, by: {by:?}"
+        );
+    };
+    for a in -5..=5 {
+        check(&[a], &[3, 4]);
+        for b in -5..=5 {
+            check(&[a, b], &[3, 4]);
+            check(&[a, b], &[2, 3, 4]);
+            for c in -5..=5 {
+                check(&[a, b, c], &[2, 3, 4]);
+            }
+        }
+    }
+    // Empty arrays and huge offsets
+    check(&[1, 1], &[2, 0]);
+    check(&[1, 1, 1], &[0, 2, 3]);
+    check(&[isize::MAX, isize::MIN + 1], &[3, 4]);
+}
+
 impl Value {
     /// Use this array to `windows` another
     pub fn windows(&self, from: &Self, env: &Uiua) -> UiuaResult<Self> {
-        let size_spec = self.as_ints(env, "Window size must be a list of integers")?;
-        Ok(match from {
-            Value::Num(a) => a.windows(&size_spec, env)?.into(),
-            #[cfg(feature = "bytes")]
-            Value::Byte(a) => a.windows(&size_spec, env)?.into(),
-            Value::Complex(a) => a.windows(&size_spec, env)?.into(),
-            Value::Char(a) => a.windows(&size_spec, env)?.into(),
-            Value::Box(a) => a.windows(&size_spec, env)?.into(),
+        self.windows_impl(from, false, env)
+    }
+    /// Use this array to `windows` another, boxing each window
+    pub fn boxed_windows(&self, from: &Self, env: &Uiua) -> UiuaResult<Self> {
+        self.windows_impl(from, true, env)
+    }
+    fn windows_impl(&self, from: &Self, boxed: bool, env: &Uiua) -> UiuaResult<Self> {
+        let size_spec =
+            (self.unboxed_ref()).as_ints(env, "Window size must be a list of integers")?;
+        Ok(if boxed {
+            match from {
+                Value::Num(a) => a.boxed_windows(&size_spec, env)?.into(),
+                #[cfg(feature = "bytes")]
+                Value::Byte(a) => a.boxed_windows(&size_spec, env)?.into(),
+                Value::Complex(a) => a.boxed_windows(&size_spec, env)?.into(),
+                Value::Char(a) => a.boxed_windows(&size_spec, env)?.into(),
+                Value::Box(a) => a.boxed_windows(&size_spec, env)?.into(),
+            }
+        } else {
+            match from {
+                Value::Num(a) => a.windows(&size_spec, env)?.into(),
+                #[cfg(feature = "bytes")]
+                Value::Byte(a) => a.windows(&size_spec, env)?.into(),
+                Value::Complex(a) => a.windows(&size_spec, env)?.into(),
+                Value::Char(a) => a.windows(&size_spec, env)?.into(),
+                Value::Box(a) => a.windows(&size_spec, env)?.into(),
+            }
         })
     }
+    /// Call a function on each of this value's `windows`
+    ///
+    /// See [`Array::for_each_window`].
+    /// Iteration stops at the first error returned by `f`, which is returned.
+    pub fn for_each_window(
+        &self,
+        size_spec: &[isize],
+        env: &Uiua,
+        mut f: impl FnMut(Value) -> UiuaResult,
+    ) -> UiuaResult {
+        match self {
+            Value::Num(a) => a.for_each_window(size_spec, env, |w| f(w.into())),
+            #[cfg(feature = "bytes")]
+            Value::Byte(a) => a.for_each_window(size_spec, env, |w| f(w.into())),
+            Value::Complex(a) => a.for_each_window(size_spec, env, |w| f(w.into())),
+            Value::Char(a) => a.for_each_window(size_spec, env, |w| f(w.into())),
+            Value::Box(a) => a.for_each_window(size_spec, env, |w| f(w.into())),
+        }
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
-    /// Get the `windows` of this array
-    pub fn windows(&self, isize_spec: &[isize], env: &Uiua) -> UiuaResult<Self> {
+    /// Resolve a `windows` size spec into the window size along each axis
+    ///
+    /// A negative size `s` on an axis of length `d` means a window of
+    /// length `d + 1 + s`, which makes `|s|` windows along that axis.
+    fn window_size_spec(&self, isize_spec: &[isize], env: &Uiua) -> UiuaResult<Vec<usize>> {
         if isize_spec.iter().any(|&s| s == 0) {
             return Err(env.error("Window size cannot be zero"));
         }
@@ -808,17 +2048,87 @@ impl<T: ArrayValue> Array<T> {
                 (*d as isize + 1 + *s).max(0) as usize
             });
         }
+        if let Some(i) = size_spec.iter().position(|&size| size == 0) {
+            return Err(env.error(format!(
+                "Window size {} is a window of length 0 for axis of length {}",
+                isize_spec[i], self.shape[i]
+            )));
+        }
+        Ok(size_spec)
+    }
+    /// Get the `windows` of this array, with each window in its own box
+    ///
+    /// The boxes are laid out in the grid of window positions.
+    pub fn boxed_windows(&self, isize_spec: &[isize], env: &Uiua) -> UiuaResult<Array<Boxed>>
+    where
+        Self: Into<Value>,
+    {
+        let size_spec = self.window_size_spec(isize_spec, env)?;
+        let grid_shape: Shape = (self.shape.iter().zip(&size_spec))
+            .map(|(s, w)| s + 1 - w)
+            .collect();
+        // Each window is copied straight into its box
+        let mut boxes = EcoVec::with_capacity(grid_shape.iter().product());
+        self.for_each_window(isize_spec, env, |window| {
+            boxes.push(Boxed(window.into()));
+            Ok(())
+        })?;
+        Ok(Array::new(grid_shape, boxes))
+    }
+    /// Get the `windows` of this array
+    pub fn windows(&self, isize_spec: &[isize], env: &Uiua) -> UiuaResult<Self> {
+        self.dilated_windows(isize_spec, &[], env)
+    }
+    /// Get the `windows` of this array, with the items of each window spaced apart
+    ///
+    /// Along each axis, consecutive items of a window are `dilation` items apart
+    /// in this array, so a window of size `n` spans `(n - 1) * dilation + 1` items.
+    /// Axes without a dilation have a dilation of 1, which is the same as [`Array::windows`].
+    pub fn dilated_windows(
+        &self,
+        isize_spec: &[isize],
+        dilation: &[usize],
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        let size_spec = self.window_size_spec(isize_spec, env)?;
+        if dilation.len() > size_spec.len() {
+            return Err(env.error(format!(
+                "Window dilation {dilation:?} has more axes than window size {isize_spec:?}"
+            )));
+        }
+        if dilation.contains(&0) {
+            return Err(env.error("Window dilation cannot be zero"));
+        }
+        let mut true_dilation = vec![1; self.shape.len()];
+        true_dilation[..dilation.len()].copy_from_slice(dilation);
+        // The number of items each window spans along each axis
+        let spans: Vec<usize> = (size_spec.iter().zip(&true_dilation))
+            .map(|(&size, &dil)| (size - 1).saturating_mul(dil).saturating_add(1))
+            .collect();
+        for ((&span, &dil), &len) in spans.iter().zip(&true_dilation).zip(&self.shape) {
+            if dil > 1 && span > len {
+                return Err(env.error(format!(
+                    "Window with dilation {dil} spans {span} items, \
+                    which is too large for axis of length {len}"
+                )));
+            }
+        }
         // Determine the shape of the windows array
         let mut new_shape = Shape::with_capacity(self.shape.len() + size_spec.len());
-        new_shape.extend(self.shape.iter().zip(&size_spec).map(|(a, b)| a + 1 - *b));
+        new_shape.extend(self.shape.iter().zip(&spans).map(|(a, b)| a + 1 - *b));
         new_shape.extend_from_slice(&size_spec);
         new_shape.extend_from_slice(&self.shape[size_spec.len()..]);
+        checked_element_count::<T>(new_shape.iter().copied(), "Windows", env)?;
         // Check if the window size is too large
         for (size, sh) in size_spec.iter().zip(&self.shape) {
             if *size > *sh {
                 return Ok(Self::new(new_shape, CowSlice::new()));
             }
         }
+        // An axis that is not windowed may be empty
+        if self.data.is_empty() {
+            return Ok(Self::new(new_shape, CowSlice::new()));
+        }
         // Make a new window shape with the same rank as the windowed array
         let mut true_size: Vec<usize> = Vec::with_capacity(self.shape.len());
         true_size.extend(size_spec);
@@ -828,48 +2138,462 @@ impl<T: ArrayValue> Array<T> {
 
         let mut dst = EcoVec::from_elem(self.data[0].clone(), new_shape.iter().product());
         let dst_slice = dst.make_mut();
-        let mut corner = vec![0; self.shape.len()];
-        let mut curr = vec![0; self.shape.len()];
-        let mut k = 0;
-        'windows: loop {
-            // Reset curr
-            for i in curr.iter_mut() {
-                *i = 0;
-            }
-            // Copy the window at the current corner
-            'items: loop {
-                // Copy the current item
-                let mut src_index = 0;
-                let mut stride = 1;
-                for ((c, i), s) in corner.iter().zip(&curr).zip(&self.shape).rev() {
-                    src_index += (*c + *i) * stride;
-                    stride *= s;
-                }
-                dst_slice[k] = self.data[src_index].clone();
-                k += 1;
-                // Go to the next item
-                for i in (0..curr.len()).rev() {
-                    if curr[i] == true_size[i] - 1 {
-                        curr[i] = 0;
-                    } else {
-                        curr[i] += 1;
-                        continue 'items;
-                    }
-                }
+        let window_len: usize = true_size.iter().product();
+        let corner_shape: Vec<usize> = (self.shape.iter().zip(&true_size).zip(&true_dilation))
+            .map(|((s, t), d)| s + 1 - ((t - 1) * d + 1))
+            .collect();
+        let strides = shape_strides(&self.shape);
+        let steps: Vec<usize> = strides
+            .iter()
+            .zip(&true_dilation)
+            .map(|(s, d)| s * d)
+            .collect();
+        // The corner and position buffers are reused across windows
+        let scratch = || (vec![0; corner_shape.len()], vec![0; corner_shape.len()]);
+        // Each window writes to its own contiguous chunk of the output
+        let parallel = dst_slice.len() / window_len > 500;
+        let op = |(corner, curr): &mut (Vec<usize>, Vec<usize>), i, window: &mut [T]| {
+            data_index_to_shape_index(i, &corner_shape, corner);
+            let src_indices = window_src_indices(corner, &true_size, &strides, &steps, curr);
+            for (item, src_index) in window.iter_mut().zip(src_indices) {
+                *item = self.data[src_index].clone();
+            }
+        };
+        for_each_chunk(dst_slice, window_len, parallel, scratch, op);
+        Ok(Array::new(new_shape, dst))
+    }
+    /// Reduce each of this array's `windows` with a function
+    ///
+    /// This is equivalent to folding each window of [`Array::windows`],
+    /// but the windows are never materialized.
+    /// The resulting shape is the number of windows along each windowed axis.
+    pub fn windows_reduce<A>(
+        &self,
+        isize_spec: &[isize],
+        init: A,
+        f: impl Fn(A, &T) -> A + Sync,
+        env: &Uiua,
+    ) -> UiuaResult<Array<A>>
+    where
+        A: Clone + Send + Sync,
+    {
+        let size_spec = self.window_size_spec(isize_spec, env)?;
+        let new_shape: Shape = (self.shape.iter().zip(&size_spec))
+            .map(|(a, b)| a + 1 - *b)
+            .collect();
+        let mut true_size = size_spec;
+        true_size.extend(&self.shape[true_size.len()..]);
+        let corner_shape: Vec<usize> = (self.shape.iter().zip(&true_size))
+            .map(|(s, t)| s + 1 - t)
+            .collect();
+        let mut dst = EcoVec::from_elem(init.clone(), new_shape.iter().product());
+        let dst_slice = dst.make_mut();
+        let strides = shape_strides(&self.shape);
+        let scratch = || (vec![0; corner_shape.len()], vec![0; corner_shape.len()]);
+        let op = |(corner, curr): &mut (Vec<usize>, Vec<usize>), i, acc: &mut [A]| {
+            data_index_to_shape_index(i, &corner_shape, corner);
+            let src_indices = window_src_indices(corner, &true_size, &strides, &strides, curr);
+            acc[0] = src_indices.fold(init.clone(), |acc, src_index| f(acc, &self.data[src_index]));
+        };
+        let parallel = dst_slice.len() > 500;
+        for_each_chunk(dst_slice, 1, parallel, scratch, op);
+        Ok(Array::new(new_shape, dst))
+    }
+    /// Call a function on each of this array's `windows`
+    ///
+    /// The windows are the rows of [`Array::windows`] in the same order,
+    /// but only one is built at a time.
+    /// Iteration stops at the first error returned by `f`, which is returned.
+    pub fn for_each_window(
+        &self,
+        isize_spec: &[isize],
+        env: &Uiua,
+        mut f: impl FnMut(Self) -> UiuaResult,
+    ) -> UiuaResult {
+        let size_spec = self.window_size_spec(isize_spec, env)?;
+        let mut true_size = size_spec;
+        true_size.extend(&self.shape[true_size.len()..]);
+        let corner_shape: Vec<usize> = (self.shape.iter().zip(&true_size))
+            .map(|(s, t)| s + 1 - t)
+            .collect();
+        let strides = shape_strides(&self.shape);
+        let mut corner = vec![0; corner_shape.len()];
+        let mut curr = vec![0; corner_shape.len()];
+        for i in 0..corner_shape.iter().product() {
+            data_index_to_shape_index(i, &corner_shape, &mut corner);
+            let src_indices =
+                window_src_indices(&corner, &true_size, &strides, &strides, &mut curr);
+            let data: CowSlice<T> = src_indices.map(|i| self.data[i].clone()).collect();
+            f(Array::new(Shape::from(true_size.as_slice()), data))?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterate over the data indices of the items in a window, given the window's corner
+///
+/// `strides` are the strides of the windowed array's shape, and consecutive
+/// items of the window along each axis are `steps` apart in its data.
+/// `curr` is scratch space for the position in the window, with one item per axis.
+fn window_src_indices<'a>(
+    corner: &[usize],
+    size: &'a [usize],
+    strides: &[usize],
+    steps: &'a [usize],
+    curr: &'a mut [usize],
+) -> impl Iterator<Item = usize> + 'a {
+    let window_len: usize = size.iter().product();
+    // The index moves by a fixed step along each axis,
+    // so it is kept in step with `curr` rather than recomputed
+    curr.fill(0);
+    let mut src_index: usize = corner.iter().zip(strides).map(|(c, s)| c * s).sum();
+    (0..window_len).map(move |_| {
+        let index = src_index;
+        // Go to the next item
+        for i in (0..curr.len()).rev() {
+            if curr[i] == size[i] - 1 {
+                src_index -= curr[i] * steps[i];
+                curr[i] = 0;
+            } else {
+                curr[i] += 1;
+                src_index += steps[i];
                 break;
             }
-            // Go to the next corner
-            for i in (0..corner.len()).rev() {
-                if corner[i] == self.shape[i] - true_size[i] {
-                    corner[i] = 0;
+        }
+        index
+    })
+}
+
+#[test]
+fn dilated_windows() {
+    let env = Uiua::with_native_sys();
+    let list = Array::<f64>::from_iter((0..10).map(|i| i as f64));
+    let windows = list.dilated_windows(&[3], &[2], &env).unwrap();
+    assert_eq!(&*windows.shape, [6, 3]);
+    assert_eq!(windows.data[..6], [0.0, 2.0, 4.0, 1.0, 3.0, 5.0]);
+    assert_eq!(windows.data[15..], [5.0, 7.0, 9.0]);
+    // A dilation of 1 is the same as plain windows
+    for spec in [vec![3], vec![-2], vec![10]] {
+        let plain = list.windows(&spec, &env).unwrap();
+        assert_eq!(list.dilated_windows(&spec, &[1], &env).unwrap(), plain);
+    }
+
+    let grid = Array::<f64>::new(
+        tinyvec::tiny_vec![5, 5],
+        (0..25).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    let windows = grid.dilated_windows(&[2, 2], &[2, 3], &env).unwrap();
+    assert_eq!(&*windows.shape, [3, 2, 2, 2]);
+    assert_eq!(windows.data[..4], [0.0, 3.0, 10.0, 13.0]);
+    assert_eq!(windows.data[20..], [11.0, 14.0, 21.0, 24.0]);
+    // Undilated axes are contiguous
+    let windows = grid.dilated_windows(&[2, 2], &[2], &env).unwrap();
+    assert_eq!(&*windows.shape, [3, 4, 2, 2]);
+    assert_eq!(windows.data[..4], [0.0, 1.0, 10.0, 11.0]);
+
+    // Windows that do not fit, zero dilations, and extra dilations are errors
+    assert!(list.dilated_windows(&[4], &[4], &env).is_err());
+    assert!(list.dilated_windows(&[3], &[0], &env).is_err());
+    assert!(grid.dilated_windows(&[2], &[1, 1], &env).is_err());
+}
+
+#[test]
+fn windows_empty() {
+    let env = Uiua::with_native_sys();
+    let empty = |shape: &[usize]| Array::<f64>::new(Shape::from(shape), CowSlice::new());
+    // Empty axes that are not windowed are kept
+    let windows = empty(&[3, 0]).windows(&[1], &env).unwrap();
+    assert_eq!(&*windows.shape, [3, 1, 0]);
+    let windows = empty(&[3, 0, 2]).windows(&[2], &env).unwrap();
+    assert_eq!(&*windows.shape, [2, 2, 0, 2]);
+    let windows = empty(&[3, 0]).dilated_windows(&[2], &[2], &env).unwrap();
+    assert_eq!(&*windows.shape, [1, 2, 0]);
+    // Windowing an empty axis is an error
+    assert!(empty(&[0, 3]).windows(&[1, 1], &env).is_err());
+}
+
+#[test]
+fn windows_reduce_matches_windows() {
+    let env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![5, 4],
+        (0..20).map(|i| i as f64).collect::<EcoVec<_>>(),
+    );
+    for spec in [vec![2], vec![3, 2], vec![-2, 4], vec![5, 1]] {
+        let windows = arr.windows(&spec, &env).unwrap();
+        let reduced = arr
+            .windows_reduce(&spec, 0.0, |acc, x| acc + x, &env)
+            .unwrap();
+        let window_count: usize = reduced.shape.iter().product();
+        assert_eq!(reduced.shape[..], windows.shape[..reduced.rank()]);
+        let expected: Vec<f64> = (windows.data.chunks(windows.element_count() / window_count))
+            .map(|window| window.iter().sum())
+            .collect();
+        assert_eq!(reduced.data.as_slice(), expected, "spec: {spec:?}");
+    }
+    assert!(arr
+        .windows_reduce(&[6], 0.0, |acc, x| acc + x, &env)
+        .is_err());
+}
+
+#[test]
+fn for_each_window() {
+    let env = Uiua::with_native_sys();
+    let arr = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![4, 3],
+        (0..12).map(|i| i as f64).collect::<EcoVec<_>>(),
+    ));
+    // The windows are the rows of the windows array
+    for spec in [vec![2], vec![2, 2], vec![-1, 3], vec![4]] {
+        let spec_value = Value::from_iter(spec.iter().map(|&s| s as f64));
+        let windows = spec_value.windows(&arr, &env).unwrap();
+        let window_shape = Shape::from(&windows.shape()[spec.len()..]);
+        let mut visited = Vec::new();
+        arr.for_each_window(&spec, &env, |w| {
+            visited.push(w);
+            Ok(())
+        })
+        .unwrap();
+        let expected: Vec<Value> = windows.row_shaped_slices(window_shape).collect();
+        assert_eq!(visited, expected, "spec: {spec:?}");
+    }
+    // A running maximum without building the windows
+    let list = Value::from_iter([3.0, 1.0, 4.0, 1.0, 5.0]);
+    let mut maxes = Vec::new();
+    list.for_each_window(&[2], &env, |w| {
+        let Value::Num(w) = w else { unreachable!() };
+        maxes.push(w.data.iter().copied().fold(f64::MIN, f64::max));
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(maxes, [3.0, 4.0, 4.0, 5.0]);
+    // Characters stay characters
+    let mut words = Vec::new();
+    Value::from("abc")
+        .for_each_window(&[2], &env, |w| {
+            words.push(w);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(words, [Value::from("ab"), Value::from("bc")]);
+    // The first error stops iteration
+    let mut calls = 0;
+    let err = list.for_each_window(&[1], &env, |_| {
+        calls += 1;
+        if calls == 2 {
+            Err(env.error("stop"))
+        } else {
+            Ok(())
+        }
+    });
+    assert_eq!(calls, 2);
+    assert!(err.unwrap_err().to_string().contains("stop"));
+    // Bad specs are errors before any window is visited
+    assert!(list.for_each_window(&[6], &env, |_| panic!()).is_err());
+    assert!(list.for_each_window(&[0], &env, |_| panic!()).is_err());
+}
+
+#[test]
+fn boxed_windows() {
+    let env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![3, 3],
+        (0..9).map(|i| i as f64).collect::<EcoVec<_>>(),
+    );
+    for spec in [vec![2], vec![2, 2], vec![-1, 3]] {
+        let windows = arr.windows(&spec, &env).unwrap();
+        let boxed = arr.boxed_windows(&spec, &env).unwrap();
+        assert_eq!(boxed.shape[..], windows.shape[..spec.len()], "{spec:?}");
+        let window_shape = Shape::from(&windows.shape[spec.len()..]);
+        for (b, w) in boxed
+            .data
+            .iter()
+            .zip(windows.row_shaped_slices(window_shape))
+        {
+            assert_eq!(b.0, w.into(), "{spec:?}");
+        }
+    }
+    // Windows of rows with no items are still boxed
+    let empty_rows = Array::<f64>::new(tinyvec::tiny_vec![3, 0], CowSlice::new());
+    let boxed = empty_rows.boxed_windows(&[2], &env).unwrap();
+    assert_eq!(boxed.shape[..], [2]);
+    assert!((boxed.data.iter()).all(|b| b.0.shape() == [2, 0]));
+    // Through `Value`
+    let boxed = Value::from(2.0)
+        .boxed_windows(&Value::from_iter([1.0, 2.0, 3.0]), &env)
+        .unwrap();
+    let expected: Array<Boxed> = [[1.0, 2.0], [2.0, 3.0]]
+        .into_iter()
+        .map(|w| Boxed(Value::from_iter(w)))
+        .collect();
+    assert_eq!(boxed, expected.into());
+}
+
+#[test]
+fn negative_window_sizes() {
+    let env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![4, 3],
+        (0..12).map(|i| i as f64).collect::<EcoVec<_>>(),
+    );
+    // A size of `s < 0` is a window of length `d + 1 + s`
+    for (neg, pos) in [(-1, 4), (-2, 3), (-4, 1)] {
+        let windows = arr.windows(&[neg], &env).unwrap();
+        assert_eq!(windows, arr.windows(&[pos], &env).unwrap(), "{neg}");
+        assert_eq!(windows.row_count(), neg.unsigned_abs());
+    }
+    assert_eq!(
+        arr.windows(&[-2, -3], &env).unwrap(),
+        arr.windows(&[3, 1], &env).unwrap()
+    );
+    // Sizes that would make windows of length 0 or less are errors,
+    // not empty results
+    for spec in [vec![0], vec![-5], vec![2, -4]] {
+        assert!(arr.windows(&spec, &env).is_err(), "{spec:?}");
+    }
+}
+
+impl Array<f64> {
+    /// Reconstruct the array that strided `windows` were taken from
+    ///
+    /// Items covered by more than one window are summed.
+    /// Items covered by no window are set to the fill value.
+    pub fn unwindows_stride(
+        &self,
+        size_spec: &[usize],
+        stride_spec: &[usize],
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        let axes = size_spec.len();
+        if stride_spec.len() != axes {
+            return Err(env.error(format!(
+                "Window size {size_spec:?} and stride {stride_spec:?} \
+                must have the same number of axes"
+            )));
+        }
+        if stride_spec.contains(&0) {
+            return Err(env.error("Window stride cannot be zero"));
+        }
+        if self.rank() < axes * 2 || self.shape[axes..axes * 2] != *size_spec {
+            return Err(env.error(format!(
+                "Cannot unwindows array with shape {} with window size {size_spec:?}",
+                self.format_shape()
+            )));
+        }
+        // Determine the shape of the original array
+        let mut shape: Shape = (self.shape[..axes].iter().zip(size_spec).zip(stride_spec))
+            .map(|((&count, &size), &stride)| {
+                if count == 0 {
+                    0
                 } else {
-                    corner[i] += 1;
-                    continue 'windows;
+                    (count - 1) * stride + size
                 }
+            })
+            .collect();
+        shape.extend_from_slice(&self.shape[axes * 2..]);
+        // Sum each window item into its place in the original array
+        let len = shape.iter().product();
+        let mut data = eco_vec![0.0; len];
+        let slice = data.make_mut();
+        let mut covered = vec![false; len];
+        let mut index = vec![0; self.rank()];
+        for (i, &x) in self.data.iter().enumerate() {
+            data_index_to_shape_index(i, &self.shape, &mut index);
+            let mut j = 0;
+            for (a, &s) in shape.iter().enumerate() {
+                let pos = if a < axes {
+                    index[a] * stride_spec[a] + index[axes + a]
+                } else {
+                    index[axes + a]
+                };
+                j = j * s + pos;
             }
-            break Ok(Array::new(new_shape, dst));
+            slice[j] += x;
+            covered[j] = true;
+        }
+        // Fill any gaps between windows
+        if covered.contains(&false) {
+            let fill = env.fill::<f64>().map_err(|e| {
+                env.error(format!(
+                    "Cannot unwindows with gaps between windows without a fill value{e}"
+                ))
+                .fill()
+            })?;
+            for (x, covered) in slice.iter_mut().zip(covered) {
+                if !covered {
+                    *x = fill;
+                }
+            }
+        }
+        Ok(Array::new(shape, data))
+    }
+}
+
+#[cfg(test)]
+fn windows_stride(arr: &Array<f64>, size_spec: &[usize], stride_spec: &[usize]) -> Array<f64> {
+    let axes = size_spec.len();
+    let counts: Vec<usize> = (arr.shape.iter().zip(size_spec).zip(stride_spec))
+        .map(|((&len, &size), &stride)| (len - size) / stride + 1)
+        .collect();
+    let mut shape: Shape = counts.iter().chain(size_spec).copied().collect();
+    shape.extend_from_slice(&arr.shape[axes..]);
+    let mut data = EcoVec::new();
+    let mut index = vec![0; shape.len()];
+    for i in 0..shape.iter().product() {
+        data_index_to_shape_index(i, &shape, &mut index);
+        let mut j = 0;
+        for (a, &s) in arr.shape.iter().enumerate() {
+            let pos = if a < axes {
+                index[a] * stride_spec[a] + index[axes + a]
+            } else {
+                index[axes + a]
+            };
+            j = j * s + pos;
         }
+        data.push(arr.data[j]);
     }
+    Array::new(shape, data)
+}
+
+#[test]
+fn unwindows_stride() {
+    let mut env = Uiua::with_native_sys();
+    let arr = Array::<f64>::from_iter((0..6).map(|i| i as f64));
+
+    // Overlapping windows are summed
+    let windows = windows_stride(&arr, &[3], &[1]);
+    let unwindowed = windows.unwindows_stride(&[3], &[1], &env).unwrap();
+    let expected = Array::from_iter([0.0, 2.0, 6.0, 9.0, 8.0, 5.0]);
+    assert_eq!(unwindowed, expected);
+
+    // Non-overlapping windows are restored exactly
+    let windows = windows_stride(&arr, &[2], &[2]);
+    assert_eq!(windows.unwindows_stride(&[2], &[2], &env).unwrap(), arr);
+
+    // Gaps between windows require a fill
+    let windows = windows_stride(&arr, &[1], &[3]);
+    assert!(windows.unwindows_stride(&[1], &[3], &env).is_err());
+    env.with_fill(Value::from(-1.0), |env| {
+        let unwindowed = windows.unwindows_stride(&[1], &[3], env).unwrap();
+        let expected = Array::from_iter([0.0, -1.0, -1.0, 3.0]);
+        assert_eq!(unwindowed, expected);
+        Ok(())
+    })
+    .unwrap();
+
+    // Multidimensional windows
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![4, 4],
+        (0..16).map(|i| i as f64).collect::<EcoVec<_>>(),
+    );
+    let windows = windows_stride(&arr, &[2, 2], &[2, 2]);
+    assert_eq!(
+        windows.unwindows_stride(&[2, 2], &[2, 2], &env).unwrap(),
+        arr
+    );
+
+    // Mismatched window sizes are an error
+    assert!(windows.unwindows_stride(&[3, 2], &[2, 2], &env).is_err());
 }
 
 impl Value {
@@ -891,11 +2615,138 @@ impl Value {
             },
         )
     }
+    /// Find a list in each row along the last axis of this value
+    pub fn find_rows(&self, needle: &Self, env: &Uiua) -> UiuaResult<Self> {
+        self.generic_bin_ref(
+            needle,
+            |a, b| a.find_rows(b, env).map(Into::into),
+            |a, b| a.find_rows(b, env).map(Into::into),
+            |a, b| a.find_rows(b, env).map(Into::into),
+            |a, b| a.find_rows(b, env).map(Into::into),
+            |a, b| a.find_rows(b, env).map(Into::into),
+            |a, b| {
+                env.error(format!(
+                    "Cannot find {} in {} array",
+                    b.type_name(),
+                    a.type_name()
+                ))
+            },
+        )
+    }
+    /// Count how many times this value occurs in another
+    ///
+    /// This is the number of matches that `find` would mark,
+    /// including overlapping ones, without building the mask.
+    pub fn count_occurrences(&self, searched: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let count = self.generic_bin_ref(
+            searched,
+            |a, b| a.count_occurrences(b, env),
+            |a, b| a.count_occurrences(b, env),
+            |a, b| a.count_occurrences(b, env),
+            |a, b| a.count_occurrences(b, env),
+            |a, b| a.count_occurrences(b, env),
+            |a, b| {
+                env.error(format!(
+                    "Cannot find {} in {} array",
+                    a.type_name(),
+                    b.type_name()
+                ))
+            },
+        )?;
+        Ok((count as f64).into())
+    }
+    /// Get the flat index of the first window of another value that matches this one
+    ///
+    /// See [`Array::window_index_of`].
+    pub fn window_index_of(&self, searched: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let index = self.generic_bin_ref(
+            searched,
+            |a, b| Ok(a.window_index_of(b)),
+            |a, b| Ok(a.window_index_of(b)),
+            |a, b| Ok(a.window_index_of(b)),
+            |a, b| Ok(a.window_index_of(b)),
+            |a, b| Ok(a.window_index_of(b)),
+            |a, b| {
+                env.error(format!(
+                    "Cannot find {} in {} array",
+                    a.type_name(),
+                    b.type_name()
+                ))
+            },
+        )?;
+        Ok((index as f64).into())
+    }
+    /// Get the first window of this value that matches `needle`
+    ///
+    /// See [`Array::first_match`].
+    pub fn first_match(&self, needle: &Self, env: &Uiua) -> UiuaResult<Option<Self>> {
+        self.generic_bin_ref(
+            needle,
+            |a, b| Ok(a.first_match(b).map(Into::into)),
+            |a, b| Ok(a.first_match(b).map(Into::into)),
+            |a, b| Ok(a.first_match(b).map(Into::into)),
+            |a, b| Ok(a.first_match(b).map(Into::into)),
+            |a, b| Ok(a.first_match(b).map(Into::into)),
+            |a, b| {
+                env.error(format!(
+                    "Cannot find {} in {} array",
+                    b.type_name(),
+                    a.type_name()
+                ))
+            },
+        )
+    }
+    /// Check whether this list starts with another
+    ///
+    /// See [`Array::starts_with`].
+    pub fn starts_with(&self, needle: &Self, env: &Uiua) -> UiuaResult<Self> {
+        self.anchored_match(needle, false, env)
+    }
+    /// Check whether this list ends with another
+    ///
+    /// See [`Array::ends_with`].
+    pub fn ends_with(&self, needle: &Self, env: &Uiua) -> UiuaResult<Self> {
+        self.anchored_match(needle, true, env)
+    }
+    fn anchored_match(&self, needle: &Self, at_end: bool, env: &Uiua) -> UiuaResult<Self> {
+        self.generic_bin_ref(
+            needle,
+            |a, b| a.anchored_match(b, at_end, env).map(Into::into),
+            |a, b| a.anchored_match(b, at_end, env).map(Into::into),
+            |a, b| a.anchored_match(b, at_end, env).map(Into::into),
+            |a, b| a.anchored_match(b, at_end, env).map(Into::into),
+            |a, b| a.anchored_match(b, at_end, env).map(Into::into),
+            |a, b| {
+                env.error(format!(
+                    "Cannot find {} in {} array",
+                    b.type_name(),
+                    a.type_name()
+                ))
+            },
+        )
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
     /// Try to `find` this array in another
     pub fn find(&self, searched: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
+        self.find_impl(searched, None, env)
+    }
+    /// Try to `find` this array in another, where items of this array
+    /// equal to `wildcard` match any item
+    ///
+    /// The result has the same shape as that of `find`.
+    /// Because the wildcard is an ordinary value, a needle that needs to
+    /// match the wildcard value itself cannot be searched for this way.
+    pub fn find_wildcard(&self, wildcard: T, searched: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
+        self.find_impl(searched, Some(&wildcard), env)
+    }
+    fn find_impl(
+        &self,
+        searched: &Self,
+        wildcard: Option<&T>,
+        env: &Uiua,
+    ) -> UiuaResult<Array<u8>> {
         let searched_for = self;
         let mut searched = searched;
         let mut local_searched: Self;
@@ -935,75 +2786,586 @@ impl<T: ArrayValue> Array<T> {
 
         let mut data = EcoVec::from_elem(0, temp_output_shape.iter().product());
         let data_slice = data.make_mut();
-        let mut corner = vec![0; searched.shape.len()];
-        let mut curr = vec![0; searched.shape.len()];
-        let mut k = 0;
 
         if searched.shape.iter().all(|&d| d > 0) {
-            'windows: loop {
-                // Reset curr
-                for i in curr.iter_mut() {
-                    *i = 0;
+            let matcher = WindowMatcher::new(searched, searched_for, &searched_for_shape, wildcard);
+            // The corner and position buffers are reused across windows
+            let scratch = || {
+                (
+                    vec![0; temp_output_shape.len()],
+                    vec![0; temp_output_shape.len()],
+                )
+            };
+            // Each window writes to its own item of the output
+            let op = |(corner, curr): &mut (Vec<usize>, Vec<usize>), i, found: &mut [u8]| {
+                data_index_to_shape_index(i, &temp_output_shape, corner);
+                found[0] = matcher.matches(corner, curr) as u8;
+            };
+            let parallel = data_slice.len() > 500;
+            for_each_chunk(data_slice, 1, parallel, scratch, op);
+        }
+        let mut arr = Array::new(temp_output_shape, data);
+        arr.fill_to_shape(&searched.shape[..searched_for_shape.len()], 0);
+        arr.validate_shape();
+        Ok(arr)
+    }
+    /// Find a list in each row along the last axis of this array
+    ///
+    /// Unlike `find`, matches never span more than one row.
+    /// Each row's mask marks where the needle starts, so the last axis of
+    /// the result has length `row_len - needle_len + 1`. If the needle
+    /// is longer than the rows, every mask is all `0`s of length `row_len`.
+    pub fn find_rows(&self, needle: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot find in the rows of a scalar"));
+        }
+        if needle.rank() > 1 {
+            return Err(env.error(format!(
+                "Cannot find a rank {} array in rows, as it must be a list",
+                needle.rank()
+            )));
+        }
+        let row_len = *self.shape.last().unwrap();
+        let needle = needle.data.as_slice();
+        let mut shape = self.shape.clone();
+        if needle.len() > row_len {
+            return Ok(Array::new(shape, eco_vec![0; self.element_count()]));
+        }
+        let mask_len = row_len + 1 - needle.len();
+        *shape.last_mut().unwrap() = mask_len;
+        let row_count: usize = self.shape[..self.rank() - 1].iter().product();
+        let mut data = EcoVec::with_capacity(row_count * mask_len);
+        for r in 0..row_count {
+            let row = &self.data[r * row_len..(r + 1) * row_len];
+            data.extend((0..mask_len).map(|i| {
+                let window = &row[i..i + needle.len()];
+                (window.iter().zip(needle)).all(|(a, b)| a.array_eq(b)) as u8
+            }));
+        }
+        Ok(Array::new(shape, data))
+    }
+    /// Count how many times this array occurs in another
+    ///
+    /// Overlapping matches are all counted
+    pub fn count_occurrences(&self, searched: &Self, env: &Uiua) -> UiuaResult<usize> {
+        let searched_for = self;
+        let any_dim_greater = (searched_for.shape().iter().rev())
+            .zip(searched.shape().iter().rev())
+            .any(|(a, b)| a > b);
+        if self.rank() > searched.rank() || any_dim_greater {
+            // Filling pads the searched array, so this is left to the full mask
+            return Ok(if env.fill::<T>().is_ok() {
+                let mask = self.find(searched, env)?;
+                mask.data.iter().filter(|&&found| found == 1).count()
+            } else {
+                0
+            });
+        }
+        if searched.shape.contains(&0) {
+            return Ok(0);
+        }
+
+        // Pad the shape of the searched-for array
+        let mut searched_for_shape = searched_for.shape.clone();
+        while searched_for_shape.len() < searched.shape.len() {
+            searched_for_shape.insert(0, 1);
+        }
+        // The shape of the grid of window corners
+        let corners_shape: Shape = (searched.shape.iter())
+            .zip(&searched_for_shape)
+            .map(|(s, f)| s + 1 - f)
+            .collect();
+
+        let matcher = WindowMatcher::new(searched, searched_for, &searched_for_shape, None);
+        // The corner and position buffers are reused across windows
+        let scratch = || (vec![0; corners_shape.len()], vec![0; corners_shape.len()]);
+        let matches = |(corner, curr): &mut (Vec<usize>, Vec<usize>), i: usize| {
+            data_index_to_shape_index(i, &corners_shape, corner);
+            matcher.matches(corner, curr)
+        };
+        let corner_count: usize = corners_shape.iter().product();
+        // The corners are counted in blocks, each with its own count
+        const BLOCK_LEN: usize = 64;
+        let mut counts = vec![0; (corner_count + BLOCK_LEN - 1) / BLOCK_LEN];
+        let op = |buffers: &mut (Vec<usize>, Vec<usize>), b: usize, count: &mut [usize]| {
+            let block = b * BLOCK_LEN..((b + 1) * BLOCK_LEN).min(corner_count);
+            count[0] = block.filter(|&i| matches(buffers, i)).count();
+        };
+        for_each_chunk(&mut counts, 1, corner_count > 500, scratch, op);
+        Ok(counts.into_iter().sum())
+    }
+    /// Find which of several arrays occurs at each position in this one
+    ///
+    /// Each item of the result is the 1-based index of the first needle
+    /// whose window starting at that position matches, or 0 if none match.
+    ///
+    /// As with `find`, if a needle is larger than this array along some axis,
+    /// this array is padded with the fill value for that needle.
+    /// Without a fill value, such needles never match.
+    /// Needles with a higher rank than this array never match.
+    pub fn find_any(&self, needles: &[Self], env: &Uiua) -> Array<f64> {
+        let searched = self;
+        let fill = env.fill::<T>().ok();
+        // Pad the shape of each needle, along with the array it is searched in
+        let needles: Vec<(usize, &Self, Shape, Option<Self>)> = (needles.iter().enumerate())
+            .filter(|(_, needle)| needle.rank() <= searched.rank())
+            .filter_map(|(n, needle)| {
+                let mut shape = needle.shape.clone();
+                while shape.len() < searched.shape.len() {
+                    shape.insert(0, 1);
                 }
-                // Search the window whose top-left is the current corner
-                'items: loop {
-                    // Get index for the current item in the searched array
-                    let mut searched_index = 0;
-                    let mut stride = 1;
-                    for ((c, i), s) in corner.iter().zip(&curr).zip(&searched.shape).rev() {
-                        searched_index += (*c + *i) * stride;
-                        stride *= s;
-                    }
-                    // Get index for the current item in the searched-for array
-                    let mut search_for_index = 0;
-                    let mut stride = 1;
-                    for (i, s) in curr.iter().zip(&searched_for_shape).rev() {
-                        search_for_index += *i * stride;
-                        stride *= s;
-                    }
-                    // Compare the current items in the two arrays
-                    let same = if let Some(searched_for) = searched_for.data.get(search_for_index) {
-                        searched.data[searched_index].array_eq(searched_for)
-                    } else {
-                        false
-                    };
-                    if !same {
-                        data_slice[k] = 0;
-                        k += 1;
-                        break;
-                    }
-                    // Go to the next item
-                    for i in (0..curr.len()).rev() {
-                        if curr[i] == searched_for_shape[i] - 1 {
-                            curr[i] = 0;
-                        } else {
-                            curr[i] += 1;
-                            continue 'items;
-                        }
-                    }
-                    data_slice[k] = 1;
-                    k += 1;
+                if shape.iter().zip(&searched.shape).all(|(n, s)| n <= s) {
+                    return Some((n, needle, shape, None));
+                }
+                let mut padded_shape = searched.shape.clone();
+                for (p, n) in padded_shape.iter_mut().zip(&shape) {
+                    *p = (*p).max(*n);
+                }
+                let mut padded = searched.clone();
+                padded.fill_to_shape(&padded_shape, fill.clone()?);
+                Some((n, needle, shape, Some(padded)))
+            })
+            .collect();
+        let mut data = EcoVec::from_elem(0.0, searched.element_count());
+        let data_slice = data.make_mut();
+        let matchers: Vec<(usize, WindowMatcher<T>)> = (needles.iter())
+            .map(|(n, needle, needle_shape, padded)| {
+                let searched = padded.as_ref().unwrap_or(searched);
+                (*n, WindowMatcher::new(searched, needle, needle_shape, None))
+            })
+            .collect();
+        // The corner and position buffers are reused across positions
+        let scratch = || (vec![0; searched.shape.len()], vec![0; searched.shape.len()]);
+        let op = |(corner, curr): &mut (Vec<usize>, Vec<usize>), i, found: &mut [f64]| {
+            data_index_to_shape_index(i, &searched.shape, corner);
+            for (n, matcher) in &matchers {
+                let fits = (corner.iter().zip(matcher.searched_for_shape))
+                    .zip(&matcher.searched.shape)
+                    .all(|((c, n), s)| c + n <= *s);
+                if fits && matcher.matches(corner, curr) {
+                    found[0] = (n + 1) as f64;
                     break;
                 }
-                // Go to the next corner
-                for i in (0..corner.len()).rev() {
-                    if corner[i] == searched.shape[i] - searched_for_shape[i] {
-                        corner[i] = 0;
-                    } else {
-                        corner[i] += 1;
-                        continue 'windows;
-                    }
+            }
+        };
+        let parallel = data_slice.len() > 500;
+        for_each_chunk(data_slice, 1, parallel, scratch, op);
+        Array::new(searched.shape.clone(), data)
+    }
+    /// Get the first window of this array that matches `needle`
+    ///
+    /// Windows are checked in the order of the items of the `find` mask,
+    /// stopping at the first match. The window has the shape of `needle`
+    /// padded with leading `1`s to the rank of this array.
+    /// Fill values are not used, so a needle that does not fit never matches.
+    pub fn first_match(&self, needle: &Self) -> Option<Self> {
+        self.first_match_impl(needle, None)
+    }
+    /// Get the first window of this array that matches `needle`, where items
+    /// of `needle` equal to `wildcard` match any item
+    ///
+    /// Unlike with [`Array::first_match`], the window may differ from `needle`.
+    pub fn first_match_wildcard(&self, needle: &Self, wildcard: T) -> Option<Self> {
+        self.first_match_impl(needle, Some(&wildcard))
+    }
+    /// Get the flat index of the first window of another array that
+    /// matches this one, or the other array's element count if none does
+    ///
+    /// This is the index of the first `1` in the flattened `find` mask,
+    /// found without building the mask. Fill values are not used.
+    pub fn window_index_of(&self, searched: &Self) -> usize {
+        match searched.first_match_corner(self, None) {
+            Some((corner, _)) => {
+                (corner.iter().zip(&searched.shape)).fold(0, |index, (c, s)| index * s + c)
+            }
+            None => searched.element_count(),
+        }
+    }
+    /// Check whether this list starts with `needle`
+    ///
+    /// The result is a boolean scalar. This is the first item of the `find`
+    /// mask, but only the leading items are compared.
+    /// A scalar needle is treated as a list of one item, and a needle
+    /// longer than this list never matches.
+    pub fn starts_with(&self, needle: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
+        self.anchored_match(needle, false, env)
+    }
+    /// Check whether this list ends with `needle`
+    ///
+    /// This is like [`Array::starts_with`], but compares the trailing items.
+    pub fn ends_with(&self, needle: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
+        self.anchored_match(needle, true, env)
+    }
+    fn anchored_match(&self, needle: &Self, at_end: bool, env: &Uiua) -> UiuaResult<Array<u8>> {
+        if self.rank() != 1 || needle.rank() > 1 {
+            return Err(env.error(format!(
+                "Can only check whether a list {} a list, \
+                but the arrays have ranks {} and {}",
+                if at_end { "ends with" } else { "starts with" },
+                self.rank(),
+                needle.rank()
+            )));
+        }
+        let (list, needle) = (self.data.as_slice(), needle.data.as_slice());
+        if needle.len() > list.len() {
+            return Ok(0.into());
+        }
+        let start = if at_end { list.len() - needle.len() } else { 0 };
+        let window = &list[start..start + needle.len()];
+        Ok((window.iter().zip(needle).all(|(a, b)| a.array_eq(b)) as u8).into())
+    }
+    fn first_match_impl(&self, needle: &Self, wildcard: Option<&T>) -> Option<Self> {
+        let searched = self;
+        let (corner, needle_shape) = searched.first_match_corner(needle, wildcard)?;
+        let strides = shape_strides(&searched.shape);
+        let mut curr = vec![0; needle_shape.len()];
+        let data: CowSlice<T> =
+            window_src_indices(&corner, &needle_shape, &strides, &strides, &mut curr)
+                .map(|i| searched.data[i].clone())
+                .collect();
+        Some(Array::new(needle_shape, data))
+    }
+    /// Get the corner of the first window of this array that matches `needle`,
+    /// along with the shape of `needle` padded to the rank of this array
+    fn first_match_corner(
+        &self,
+        needle: &Self,
+        wildcard: Option<&T>,
+    ) -> Option<(Vec<usize>, Shape)> {
+        let searched = self;
+        if needle.rank() > searched.rank() || searched.shape.contains(&0) {
+            return None;
+        }
+        // Pad the shape of the needle
+        let mut needle_shape = needle.shape.clone();
+        while needle_shape.len() < searched.shape.len() {
+            needle_shape.insert(0, 1);
+        }
+        if (needle_shape.iter().zip(&searched.shape)).any(|(n, s)| n > s) {
+            return None;
+        }
+        // The shape of the grid of window corners
+        let corners_shape: Shape = (searched.shape.iter())
+            .zip(&needle_shape)
+            .map(|(s, n)| s + 1 - n)
+            .collect();
+        let matcher = WindowMatcher::new(searched, needle, &needle_shape, wildcard);
+        let mut corner = vec![0; corners_shape.len()];
+        let mut curr = vec![0; corners_shape.len()];
+        let corner = (0..corners_shape.iter().product()).find_map(|i| {
+            data_index_to_shape_index(i, &corners_shape, &mut corner);
+            matcher.matches(&corner, &mut curr).then(|| corner.clone())
+        })?;
+        Some((corner, needle_shape))
+    }
+}
+
+/// Checks if `searched_for` matches windows of `searched`
+///
+/// The strides of both arrays are computed once, rather than for each window.
+struct WindowMatcher<'a, T> {
+    searched: &'a Array<T>,
+    searched_for: &'a Array<T>,
+    /// The shape of `searched_for` padded to the rank of `searched`
+    searched_for_shape: &'a [usize],
+    /// Items of `searched_for` equal to this match anything
+    wildcard: Option<&'a T>,
+    searched_strides: Vec<usize>,
+    searched_for_strides: Vec<usize>,
+}
+
+impl<'a, T: ArrayValue> WindowMatcher<'a, T> {
+    fn new(
+        searched: &'a Array<T>,
+        searched_for: &'a Array<T>,
+        searched_for_shape: &'a [usize],
+        wildcard: Option<&'a T>,
+    ) -> Self {
+        WindowMatcher {
+            searched,
+            searched_for,
+            searched_for_shape,
+            wildcard,
+            searched_strides: shape_strides(&searched.shape),
+            searched_for_strides: shape_strides(searched_for_shape),
+        }
+    }
+    /// Check if `searched_for` matches the window of `searched` whose top-left is `corner`
+    ///
+    /// `curr` is scratch space for the position in the window, with one item per axis.
+    fn matches(&self, corner: &[usize], curr: &mut [usize]) -> bool {
+        let (searched, searched_for) = (self.searched, self.searched_for);
+        let (searched_strides, searched_for_strides) =
+            (&self.searched_strides, &self.searched_for_strides);
+        curr.fill(0);
+        // The indices of the current item in each array are kept in step with `curr`
+        let mut searched_index: usize = (corner.iter().zip(searched_strides))
+            .map(|(c, s)| c * s)
+            .sum();
+        let mut search_for_index = 0;
+        'items: loop {
+            // Compare the current items in the two arrays
+            let same = if let Some(searched_for) = searched_for.data.get(search_for_index) {
+                (self.wildcard).is_some_and(|wildcard| searched_for.array_eq(wildcard))
+                    || searched.data[searched_index].array_eq(searched_for)
+            } else {
+                false
+            };
+            if !same {
+                return false;
+            }
+            // Go to the next item
+            for i in (0..curr.len()).rev() {
+                if curr[i] == self.searched_for_shape[i] - 1 {
+                    searched_index -= curr[i] * searched_strides[i];
+                    search_for_index -= curr[i] * searched_for_strides[i];
+                    curr[i] = 0;
+                } else {
+                    curr[i] += 1;
+                    searched_index += searched_strides[i];
+                    search_for_index += searched_for_strides[i];
+                    continue 'items;
                 }
-                break;
             }
+            return true;
         }
-        let mut arr = Array::new(temp_output_shape, data);
-        arr.fill_to_shape(&searched.shape[..searched_for_shape.len()], 0);
-        arr.validate_shape();
-        Ok(arr)
     }
 }
 
+#[test]
+fn starts_ends_with() {
+    let env = Uiua::with_native_sys();
+    let chars = |s: &str| Value::from(s);
+    let check = |list: &str, needle: &str| {
+        let starts = chars(list).starts_with(&chars(needle), &env).unwrap();
+        let ends = chars(list).ends_with(&chars(needle), &env).unwrap();
+        (starts, ends)
+    };
+    assert_eq!(check("hello", "he"), (1.into(), 0.into()));
+    assert_eq!(check("hello", "lo"), (0.into(), 1.into()));
+    assert_eq!(check("hello", "hello"), (1.into(), 1.into()));
+    assert_eq!(check("hello", ""), (1.into(), 1.into()));
+    assert_eq!(check("hi", "high"), (0.into(), 0.into()));
+    assert_eq!(check("", ""), (1.into(), 1.into()));
+    // Agrees with the ends of the `find` mask
+    let list = Array::<f64>::from_iter([1.0, 2.0, 2.0, 1.0]);
+    let needle = Array::<f64>::from_iter([1.0, 2.0]);
+    let mask = needle.find(&list, &env).unwrap();
+    assert_eq!(
+        list.starts_with(&needle, &env).unwrap().data[0],
+        mask.data[0]
+    );
+    assert_eq!(list.ends_with(&needle, &env).unwrap().data[0], 0);
+    // A scalar needle is a list of one item, and numbers are compared as numbers
+    let nums = Value::from_iter([1.0, 2.0, 3.0]);
+    assert_eq!(nums.ends_with(&3.0.into(), &env).unwrap(), 1.into());
+    assert!(nums.starts_with(&chars("a"), &env).is_err());
+    // Only lists can be checked
+    let table = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![2, 2],
+        [1.0, 2.0, 3.0, 4.0].into_iter().collect::<CowSlice<_>>(),
+    ));
+    assert!(table.starts_with(&nums, &env).is_err());
+    assert!(nums.starts_with(&table, &env).is_err());
+}
+
+#[test]
+fn first_match() {
+    let env = Uiua::with_native_sys();
+    let table = Array::<f64>::new(
+        tinyvec::tiny_vec![3, 4],
+        (0..12).map(|i| (i % 5) as f64).collect::<CowSlice<_>>(),
+    );
+    // [[0 1 2 3] [4 0 1 2] [3 4 0 1]]
+    let needle = Array::<f64>::new(tinyvec::tiny_vec![2, 2], [0.0, 1.0, 4.0, 0.0]);
+    assert_eq!(table.first_match(&needle), Some(needle.clone()));
+    // Lower-rank needles are padded
+    let list = Array::<f64>::from_iter([4.0, 0.0]);
+    let found = table.first_match(&list).unwrap();
+    assert_eq!(&*found.shape, [1, 2]);
+    assert_eq!(found.data.as_slice(), [4.0, 0.0]);
+    // Wildcards take their items from the match
+    let pattern = Array::<f64>::new(tinyvec::tiny_vec![2, 2], [-1.0, 0.0, -1.0, 4.0]);
+    let found = table.first_match_wildcard(&pattern, -1.0).unwrap();
+    assert_eq!(found.data.as_slice(), [4.0, 0.0, 3.0, 4.0]);
+    // No match
+    assert_eq!(table.first_match(&Array::from_iter([3.0, 3.0])), None);
+    assert_eq!(table.first_match(&Array::from_iter([0.0; 5])), None);
+    assert_eq!(list.first_match(&table), None);
+    let table = Value::from(table);
+    assert!(table.first_match(&Value::from('a'), &env).is_err());
+}
+
+#[test]
+fn window_index_of_matches_find() {
+    let env = Uiua::with_native_sys();
+    let table = Array::<f64>::new(
+        tinyvec::tiny_vec![3, 4],
+        (0..12).map(|i| (i % 5) as f64).collect::<CowSlice<_>>(),
+    );
+    // [[0 1 2 3] [4 0 1 2] [3 4 0 1]]
+    let needles = [
+        Array::<f64>::new(tinyvec::tiny_vec![2, 2], [4.0, 0.0, 3.0, 4.0]),
+        Array::from_iter([0.0, 1.0]),
+        Array::from_iter([1.0]),
+        Array::from_iter([3.0, 3.0]),
+        Array::from_iter([0.0; 5]),
+    ];
+    for needle in needles {
+        let mask = needle.find(&table, &env).unwrap();
+        let expected =
+            (mask.data.iter().position(|&found| found == 1)).unwrap_or(table.element_count());
+        assert_eq!(needle.window_index_of(&table), expected, "{needle:?}");
+    }
+    let index = Value::from(Array::from_iter([1.0, 2.0]))
+        .window_index_of(&table.into(), &env)
+        .unwrap();
+    assert_eq!(index, Value::from(1.0));
+}
+
+#[test]
+fn find_any_matches_find() {
+    let env = Uiua::with_native_sys();
+    let haystack = Array::<f64>::from_iter([1.0, 2.0, 3.0, 1.0, 2.0, 2.0, 3.0]);
+    let needles = [
+        Array::from_iter([2.0, 3.0]),
+        Array::from_iter([1.0, 2.0, 3.0]),
+        Array::from_iter([2.0]),
+    ];
+    let found = haystack.find_any(&needles, &env);
+    assert_eq!(found.data.as_slice(), [2.0, 1.0, 0.0, 0.0, 3.0, 1.0, 0.0]);
+
+    // Each position agrees with the first single-needle find that matches
+    let masks: Vec<Array<u8>> = (needles.iter())
+        .map(|needle| needle.find(&haystack, &env).unwrap())
+        .collect();
+    for (i, &n) in found.data.iter().enumerate() {
+        let first = masks.iter().position(|mask| mask.data[i] == 1);
+        assert_eq!(n, first.map_or(0.0, |n| n as f64 + 1.0));
+    }
+
+    // Needles that do not fit never match without a fill,
+    // but still count towards the indices of later needles
+    let too_big = Array::from_iter([1.0; 8]);
+    let rank_too_big = Array::new(tinyvec::tiny_vec![1, 1], cowslice![1.0]);
+    let needles = [too_big.clone(), rank_too_big, Array::from_iter([3.0])];
+    let found = haystack.find_any(&needles, &env);
+    assert_eq!(found.data.as_slice(), [0.0, 0.0, 3.0, 0.0, 0.0, 0.0, 3.0]);
+
+    // With a fill, needles that are too long are matched against the padded array
+    let mut env = env;
+    let short = Array::<f64>::from_iter([1.0, 2.0]);
+    let needles = [Array::from_iter([1.0, 2.0, 0.0]), too_big];
+    env.with_fill(Value::from(0.0), |env| {
+        let found = short.find_any(&needles, env);
+        assert_eq!(found.data.as_slice(), [1.0, 0.0]);
+        let mask = needles[0].find(&short, env)?;
+        assert_eq!(mask.data[0], 1);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(short.find_any(&needles, &env).data.as_slice(), [0.0, 0.0]);
+}
+
+#[test]
+fn find_wildcard() {
+    let env = Uiua::with_native_sys();
+    let haystack: Array<char> = "cat cot cut coat".chars().collect();
+    let needle: Array<char> = "c?t".chars().collect();
+    let found = needle.find_wildcard('?', &haystack, &env).unwrap();
+    let exact = needle.find(&haystack, &env).unwrap();
+    assert_eq!(found.shape, exact.shape);
+    let starts: Vec<usize> = (found.data.iter().enumerate())
+        .filter(|(_, &found)| found == 1)
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(starts, [0, 4, 8]);
+    assert!(exact.data.iter().all(|&found| found == 0));
+
+    // Wildcards work in any dimension
+    let grid = Array::<f64>::new(
+        tinyvec::tiny_vec![3, 3],
+        [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]
+            .into_iter()
+            .collect::<CowSlice<_>>(),
+    );
+    let corners = Array::<f64>::new(
+        tinyvec::tiny_vec![2, 2],
+        [-1.0, 6.0, -1.0, -1.0].into_iter().collect::<CowSlice<_>>(),
+    );
+    let found = corners.find_wildcard(-1.0, &grid, &env).unwrap();
+    assert_eq!(&*found.shape, [3, 3]);
+    assert_eq!(found.data.as_slice(), [0, 0, 0, 0, 1, 0, 0, 0, 0]);
+}
+
+#[test]
+fn find_rows() {
+    let env = Uiua::with_native_sys();
+    let text = |rows: &[&str]| {
+        let width = rows[0].chars().count();
+        Array::<char>::new(
+            tinyvec::tiny_vec![rows.len(), width],
+            rows.iter()
+                .flat_map(|row| row.chars())
+                .collect::<CowSlice<_>>(),
+        )
+    };
+    let table = text(&["abcab", "cabca", "bcabc"]);
+    let needle: Array<char> = "ab".chars().collect();
+    let found = table.find_rows(&needle, &env).unwrap();
+    assert_eq!(&*found.shape, [3, 4]);
+    assert_eq!(found.data.as_slice(), [1, 0, 0, 1, 0, 1, 0, 0, 0, 0, 1, 0]);
+    // Needles longer than the rows
+    let needle: Array<char> = "abcabc".chars().collect();
+    let found = table.find_rows(&needle, &env).unwrap();
+    assert_eq!(&*found.shape, [3, 5]);
+    assert!(found.data.iter().all(|&found| found == 0));
+    // An empty needle is found everywhere
+    let found = table.find_rows(&Array::default(), &env).unwrap();
+    assert_eq!(&*found.shape, [3, 6]);
+    assert!(found.data.iter().all(|&found| found == 1));
+    assert!(table.find_rows(&table, &env).is_err());
+}
+
+#[test]
+fn count_occurrences_matches_find() {
+    let mut env = Uiua::with_native_sys();
+    let count_found = |needle: &Value, haystack: &Value, env: &Uiua| {
+        let mask = needle.find(haystack, env).unwrap();
+        let mask = (mask.as_number_array(env, "", |_| true, |_| true, |n| n)).unwrap();
+        let expected = mask.data.iter().sum::<f64>();
+        let count = needle.count_occurrences(haystack, env).unwrap();
+        assert_eq!(count, expected.into(), "{needle:?} in {haystack:?}");
+    };
+    let haystack: Value = [1.0, 1.0, 1.0, 2.0, 1.0, 1.0].into_iter().collect();
+    let needle: Value = [1.0, 1.0].into_iter().collect();
+    count_found(&needle, &haystack, &env);
+    assert_eq!(
+        needle.count_occurrences(&haystack, &env).unwrap(),
+        3.0.into()
+    );
+    let grid = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![30, 30],
+        (0..900)
+            .map(|i| (i % 7 % 2) as f64)
+            .collect::<CowSlice<_>>(),
+    ));
+    let square = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![2, 2],
+        [1.0, 0.0, 0.0, 1.0].into_iter().collect::<CowSlice<_>>(),
+    ));
+    count_found(&square, &grid, &env);
+    count_found(&Value::from(1.0), &grid, &env);
+    // Needles larger than the searched array only match with a fill
+    let long: Value = [1.0; 8].into_iter().collect();
+    count_found(&long, &haystack, &env);
+    env.with_fill(1.0.into(), |env| {
+        count_found(&long, &haystack, env);
+        Ok(())
+    })
+    .unwrap();
+}
+
 impl Value {
     /// Check which rows of this value are `member`s of another
     pub fn member(&self, of: &Self, env: &Uiua) -> UiuaResult<Self> {
@@ -1027,9 +3389,17 @@ impl Value {
 
 impl<T: ArrayValue> Array<T> {
     /// Check which rows of this array are `member`s of another
+    ///
+    /// NaN is equal to NaN unless [`Uiua::nan_never_matches`] is set.
     pub fn member(&self, of: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
         let elems = self;
+        check_depth(elems.rank().abs_diff(of.rank()), "Member", env)?;
         Ok(match elems.rank().cmp(&of.rank()) {
+            // Nothing is a member of an empty array
+            Ordering::Equal if of.row_count() == 0 => {
+                let shape: Shape = self.shape.iter().cloned().take(1).collect();
+                Array::new(shape, cowslice![0; elems.row_count()])
+            }
             Ordering::Equal => {
                 let mut result_data = EcoVec::with_capacity(elems.row_count());
                 let mut members = HashSet::with_capacity(of.row_count());
@@ -1037,7 +3407,9 @@ impl<T: ArrayValue> Array<T> {
                     members.insert(ArrayCmpSlice(of));
                 }
                 for elem in elems.row_slices() {
-                    result_data.push(members.contains(&ArrayCmpSlice(elem)) as u8);
+                    let is_member =
+                        !nan_never_matches(elem, env) && members.contains(&ArrayCmpSlice(elem));
+                    result_data.push(is_member as u8);
                 }
                 let shape: Shape = self.shape.iter().cloned().take(1).collect();
                 let res = Array::new(shape, result_data);
@@ -1053,11 +3425,17 @@ impl<T: ArrayValue> Array<T> {
             }
             Ordering::Less => {
                 if of.rank() - elems.rank() == 1 {
-                    if elems.rank() == 0 {
+                    if nan_never_matches(&elems.data, env) {
+                        Array::from(0u8)
+                    } else if elems.rank() == 0 {
                         let elem = &elems.data[0];
                         Array::from(of.data.iter().any(|of| elem.array_eq(of)) as u8)
+                    } else if elems.shape[..] != of.shape[1..] {
+                        Array::from(0u8)
                     } else {
-                        of.rows().any(|r| *elems == r).into()
+                        // Compare slices so that no row arrays are built
+                        let elem = ArrayCmpSlice(&elems.data);
+                        Array::from(of.row_slices().any(|r| ArrayCmpSlice(r) == elem) as u8)
                     }
                 } else {
                     let mut rows = Vec::with_capacity(of.row_count());
@@ -1071,16 +3449,171 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+#[test]
+fn member_sub_rank() {
+    let env = Uiua::with_native_sys();
+    let table = Array::<f64>::new(
+        tinyvec::tiny_vec![3, 2],
+        [1.0, 2.0, f64::NAN, 4.0, 5.0, 6.0]
+            .into_iter()
+            .collect::<CowSlice<_>>(),
+    );
+    let is_member = |elems: &[f64]| {
+        let elems = Array::<f64>::from_iter(elems.iter().copied());
+        let member = elems.member(&table, &env).unwrap();
+        assert_eq!(member.rank(), 0);
+        member.data[0]
+    };
+    assert_eq!(is_member(&[5.0, 6.0]), 1);
+    assert_eq!(is_member(&[f64::NAN, 4.0]), 1);
+    assert_eq!(is_member(&[6.0, 5.0]), 0);
+    // Rows of a different length are never members
+    assert_eq!(is_member(&[1.0]), 0);
+    assert_eq!(is_member(&[1.0, 2.0, 3.0]), 0);
+    assert_eq!(is_member(&[]), 0);
+}
+
+#[test]
+fn search_empty() {
+    let env = Uiua::with_native_sys();
+    let empty = Array::<f64>::from_iter([]);
+    let elems = Array::<f64>::from_iter([1.0, 2.0, f64::NAN]);
+    let member = elems.member(&empty, &env).unwrap();
+    assert_eq!(member.shape.as_slice(), [3]);
+    assert_eq!(member.data.as_slice(), [0, 0, 0]);
+    // The not-found index of an empty array is 0
+    let indices = elems.index_of(&empty, &env).unwrap();
+    assert_eq!(indices.shape.as_slice(), [3]);
+    assert_eq!(indices.data.as_slice(), [0.0, 0.0, 0.0]);
+    let indices = elems.index_of_or(&empty, -1.0, &env).unwrap();
+    assert_eq!(indices.data.as_slice(), [-1.0, -1.0, -1.0]);
+    // Searching for nothing finds nothing
+    let table = Array::<f64>::new(tinyvec::tiny_vec![0, 2], CowSlice::new());
+    let member = table.member(&table, &env).unwrap();
+    assert_eq!(member.shape.as_slice(), [0]);
+}
+
+/// Whether a row can never match another because it contains NaN
+/// and the environment says that NaN is equal to nothing
+fn nan_never_matches<T: ArrayValue>(row: &[T], env: &Uiua) -> bool {
+    env.rt.nan_never_matches && row.iter().any(T::array_is_nan)
+}
+
+impl Array<f64> {
+    /// Check which rows of this array are `member`s of another, within a tolerance
+    ///
+    /// Two rows match if each pair of corresponding numbers differs by at most `epsilon`.
+    /// As with exact [`Array::member`], NaN matches NaN and nothing else,
+    /// unless [`Uiua::nan_never_matches`] is set.
+    ///
+    /// Because nearly-equal numbers cannot be hashed together, every row is compared
+    /// against every row of `of`. This makes it quadratic where exact `member` is linear.
+    pub fn member_approx(&self, of: &Self, epsilon: f64, env: &Uiua) -> UiuaResult<Array<u8>> {
+        if epsilon.is_nan() || epsilon < 0.0 {
+            return Err(env.error(format!(
+                "Tolerance must be a non-negative number, but it is {epsilon}"
+            )));
+        }
+        let nan_eq = !env.rt.nan_never_matches;
+        let close = |a: &[f64], b: &[f64]| {
+            a.len() == b.len()
+                && (a.iter().zip(b)).all(|(a, b)| {
+                    a == b || nan_eq && a.is_nan() && b.is_nan() || (a - b).abs() <= epsilon
+                })
+        };
+        let elems = self;
+        Ok(match elems.rank().cmp(&of.rank()) {
+            Ordering::Equal => {
+                let result_data: EcoVec<u8> = (elems.row_slices())
+                    .map(|elem| of.row_slices().any(|of| close(elem, of)) as u8)
+                    .collect();
+                let shape: Shape = self.shape.iter().cloned().take(1).collect();
+                Array::new(shape, result_data)
+            }
+            Ordering::Greater => {
+                let mut rows = Vec::with_capacity(elems.row_count());
+                for elem in elems.rows() {
+                    rows.push(elem.member_approx(of, epsilon, env)?);
+                }
+                Array::from_row_arrays(rows, env)?
+            }
+            Ordering::Less => {
+                if of.rank() - elems.rank() == 1 {
+                    let is_member = elems.shape[..] == of.shape[1..]
+                        && of.row_slices().any(|of| close(&elems.data, of));
+                    Array::from(is_member as u8)
+                } else {
+                    let mut rows = Vec::with_capacity(of.row_count());
+                    for of in of.rows() {
+                        rows.push(elems.member_approx(&of, epsilon, env)?);
+                    }
+                    Array::from_row_arrays(rows, env)?
+                }
+            }
+        })
+    }
+}
+
+#[test]
+fn member_approx() {
+    let env = Uiua::with_native_sys();
+    let of = Array::from_iter([1.0, 2.0, f64::NAN, f64::INFINITY]);
+    let elems = Array::from_iter([1.05, 2.2, f64::NAN, f64::INFINITY, 0.0]);
+    let members = elems.member_approx(&of, 0.1, &env).unwrap();
+    assert_eq!(members.data.as_slice(), [1, 0, 1, 1, 0]);
+    // Exact membership is the zero-tolerance case
+    let exact = elems.member(&of, &env).unwrap();
+    assert_eq!(elems.member_approx(&of, 0.0, &env).unwrap(), exact);
+
+    let rows = Array::<f64>::new(tinyvec::tiny_vec![2, 2], [1.0, 2.0, 3.0, 4.0]);
+    let row = Array::from_iter([1.01, 1.99]);
+    let is_member = row.member_approx(&rows, 0.05, &env).unwrap();
+    assert_eq!(is_member, Array::from(1u8));
+    let is_member = row.member_approx(&rows, 0.001, &env).unwrap();
+    assert_eq!(is_member, Array::from(0u8));
+
+    assert!(elems.member_approx(&of, -1.0, &env).is_err());
+}
+
 impl Value {
     /// Get the `index of` the rows of this value in another
+    ///
+    /// Rows that are not found get the row count of the searched array.
     pub fn index_of(&self, searched_in: &Value, env: &Uiua) -> UiuaResult<Value> {
+        self.index_of_impl(searched_in, None, env)
+    }
+    /// Get the `index of` the rows of this value in another,
+    /// using `not_found` for rows that are not found
+    pub fn index_of_or(
+        &self,
+        searched_in: &Value,
+        not_found: f64,
+        env: &Uiua,
+    ) -> UiuaResult<Value> {
+        self.index_of_impl(searched_in, Some(not_found), env)
+    }
+    /// Get the `index of` the rows of this value in another,
+    /// as a byte array if every index fits in a byte
+    ///
+    /// Without the `bytes` feature, this is the same as [`Value::index_of`].
+    pub fn index_of_compressed(&self, searched_in: &Value, env: &Uiua) -> UiuaResult<Value> {
+        let mut indices = self.index_of(searched_in, env)?;
+        indices.compress();
+        Ok(indices)
+    }
+    fn index_of_impl(
+        &self,
+        searched_in: &Value,
+        not_found: Option<f64>,
+        env: &Uiua,
+    ) -> UiuaResult<Value> {
         self.generic_bin_ref(
             searched_in,
-            |a, b| a.index_of(b, env).map(Into::into),
-            |a, b| a.index_of(b, env).map(Into::into),
-            |a, b| a.index_of(b, env).map(Into::into),
-            |a, b| a.index_of(b, env).map(Into::into),
-            |a, b| a.index_of(b, env).map(Into::into),
+            |a, b| a.index_of_impl(b, not_found, env).map(Into::into),
+            |a, b| a.index_of_impl(b, not_found, env).map(Into::into),
+            |a, b| a.index_of_impl(b, not_found, env).map(Into::into),
+            |a, b| a.index_of_impl(b, not_found, env).map(Into::into),
+            |a, b| a.index_of_impl(b, not_found, env).map(Into::into),
             |a, b| {
                 env.error(format!(
                     "Cannot look for indices of {} array in {} array",
@@ -1110,11 +3643,221 @@ impl Value {
     }
 }
 
+impl Value {
+    /// Get a one-hot encoding of the rows of this value in a list of categories
+    ///
+    /// Each row becomes a list of `0`s with a `1` at the index of its category.
+    /// Rows that are not one of the categories become all `0`s,
+    /// or an error if `strict` is set.
+    pub fn one_hot(&self, categories: &Value, strict: bool, env: &Uiua) -> UiuaResult<Value> {
+        let indices = (self.index_of(categories, env)?)
+            .as_natural_array(env, "Category indices must be natural numbers")?;
+        let category_count = categories.row_count();
+        if strict {
+            if let Some(i) = indices.data.iter().position(|&i| i >= category_count) {
+                // Report where the item is along every axis
+                let mut index = vec![0; indices.rank()];
+                data_index_to_shape_index(i, &indices.shape, &mut index);
+                return Err(env.error(format!(
+                    "Cannot one-hot encode array because \
+                    the item at index {index:?} is not one of the categories"
+                )));
+            }
+        }
+        let mut shape = indices.shape.clone();
+        shape.push(category_count);
+        let mut data = eco_vec![0u8; indices.element_count() * category_count];
+        if category_count > 0 {
+            let slice = data.make_mut();
+            for (row, &i) in slice.chunks_exact_mut(category_count).zip(&indices.data) {
+                if i < category_count {
+                    row[i] = 1;
+                }
+            }
+        }
+        Ok(Array::new(shape, data).into())
+    }
+}
+
+#[test]
+fn one_hot() {
+    let env = Uiua::with_native_sys();
+    let categories = Value::from_iter([1.0, 2.0, 3.0]);
+    let values = Value::from_iter([2.0, 1.0, 3.0, 2.0]);
+    let expected = Value::from(Array::<u8>::new(
+        tinyvec::tiny_vec![4, 3],
+        [0, 1, 0, 1, 0, 0, 0, 0, 1, 0, 1, 0],
+    ));
+    assert_eq!(values.one_hot(&categories, true, &env).unwrap(), expected);
+
+    let values = Value::from_iter([3.0, 5.0]);
+    let expected = Value::from(Array::<u8>::new(
+        tinyvec::tiny_vec![2, 3],
+        [0, 0, 1, 0, 0, 0],
+    ));
+    assert_eq!(values.one_hot(&categories, false, &env).unwrap(), expected);
+    assert!(values.one_hot(&categories, true, &env).is_err());
+    // Errors give the position of the item along every axis
+    let table = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![2, 2],
+        [1.0, 2.0, 3.0, 5.0],
+    ));
+    let err = table.one_hot(&categories, true, &env).unwrap_err();
+    assert!(err.to_string().contains("index [1, 1]"), "{err}");
+}
+
+#[test]
+fn index_of_or() {
+    let env = Uiua::with_native_sys();
+    let searched_in = Array::from_iter([1.0, 2.0, 3.0]);
+    let searched_for = Array::from_iter([3.0, 9.0]);
+    let default = searched_for.index_of(&searched_in, &env).unwrap();
+    assert_eq!(default.data.as_slice(), [2.0, 3.0]);
+    let indices = searched_for.index_of_or(&searched_in, -1.0, &env).unwrap();
+    assert_eq!(indices.data.as_slice(), [2.0, -1.0]);
+
+    // The sentinel is kept through recursion
+    let table = Array::<f64>::new(tinyvec::tiny_vec![2, 2], [1.0, 2.0, 3.0, 4.0]);
+    let indices = Array::from(4.0).index_of_or(&table, -1.0, &env).unwrap();
+    assert_eq!(indices.data.as_slice(), [-1.0, 1.0]);
+}
+
+#[test]
+#[cfg(feature = "bytes")]
+fn index_of_compressed() {
+    let env = Uiua::with_native_sys();
+    let searched_in: Value = Array::<u8>::from_iter([5, 6, 7]).into();
+    let searched_for: Value = Array::<u8>::from_iter([7, 9]).into();
+    let indices = searched_for
+        .index_of_compressed(&searched_in, &env)
+        .unwrap();
+    assert_eq!(indices, Array::<u8>::from_iter([2, 3]).into());
+
+    // Indices that do not fit in a byte stay numbers
+    let searched_in: Value = Array::from_iter((0..300).map(|i| i as f64)).into();
+    let searched_for: Value = Array::from_iter([299.0, 1.0]).into();
+    let indices = searched_for
+        .index_of_compressed(&searched_in, &env)
+        .unwrap();
+    assert_eq!(indices, Array::from_iter([299.0, 1.0]).into());
+}
+
+#[test]
+fn progressive_index_of_distinct_rows() {
+    let env = Uiua::with_native_sys();
+    // Find each row in turn, using up the indices that were found
+    let reference = |of: &Array<f64>, searched_in: &Array<f64>| -> Vec<f64> {
+        let mut used = vec![false; searched_in.row_count()];
+        (of.rows())
+            .map(|row| {
+                let found = (searched_in.rows().enumerate())
+                    .position(|(i, r)| !used[i] && r == row)
+                    .unwrap_or(searched_in.row_count());
+                if found < used.len() {
+                    used[found] = true;
+                }
+                found as f64
+            })
+            .collect()
+    };
+    let list = |items: &[f64]| Array::<f64>::from_iter(items.iter().copied());
+    let table = |items: &[f64]| {
+        Array::<f64>::new(
+            tinyvec::tiny_vec![items.len() / 2, 2],
+            items.iter().copied().collect::<CowSlice<_>>(),
+        )
+    };
+    let cases = [
+        // No duplicates, where this is the same as index of
+        (list(&[3.0, 1.0, 5.0]), list(&[1.0, 2.0, 3.0, 1.0])),
+        (list(&[f64::NAN, 2.0]), list(&[2.0, f64::NAN])),
+        (
+            table(&[3.0, 4.0, 1.0, 2.0]),
+            table(&[1.0, 2.0, 3.0, 4.0, 1.0, 2.0]),
+        ),
+        // Duplicates
+        (list(&[1.0, 1.0, 1.0, 2.0]), list(&[1.0, 2.0, 1.0])),
+        (
+            table(&[1.0, 2.0, 1.0, 2.0]),
+            table(&[1.0, 2.0, 3.0, 4.0, 1.0, 2.0]),
+        ),
+    ];
+    for (i, (of, searched_in)) in cases.iter().enumerate() {
+        let found = of.progressive_index_of(searched_in, &env).unwrap();
+        assert_eq!(
+            found.data.as_slice(),
+            reference(of, searched_in),
+            "case {i}"
+        );
+        if i < 3 {
+            let index_of = of.index_of(searched_in, &env).unwrap();
+            assert_eq!(found, index_of, "case {i}");
+        }
+    }
+}
+
+#[test]
+fn nan_matching_modes() {
+    let of = Array::from_iter([1.0, f64::NAN, 3.0]);
+    let elems = Array::from_iter([f64::NAN, 3.0]);
+    let rows = Array::<f64>::new(tinyvec::tiny_vec![2, 2], [1.0, f64::NAN, 3.0, 4.0]);
+    let row = Array::from_iter([1.0, f64::NAN]);
+
+    // By default, NaN equals NaN
+    let env = Uiua::with_native_sys();
+    assert_eq!(elems.member(&of, &env).unwrap().data.as_slice(), [1, 1]);
+    let indices = elems.index_of(&of, &env).unwrap();
+    assert_eq!(indices.data.as_slice(), [1.0, 2.0]);
+    assert_eq!(row.member(&rows, &env).unwrap(), Array::from(1u8));
+    assert_eq!(row.index_of(&rows, &env).unwrap(), Array::from(0.0));
+    let approx = elems.member_approx(&of, 0.5, &env).unwrap();
+    assert_eq!(approx.data.as_slice(), [1, 1]);
+
+    // Otherwise, NaN never matches
+    let env = Uiua::with_native_sys().nan_never_matches(true);
+    assert_eq!(elems.member(&of, &env).unwrap().data.as_slice(), [0, 1]);
+    let indices = elems.index_of(&of, &env).unwrap();
+    assert_eq!(indices.data.as_slice(), [3.0, 2.0]);
+    assert_eq!(row.member(&rows, &env).unwrap(), Array::from(0u8));
+    assert_eq!(row.index_of(&rows, &env).unwrap(), Array::from(2.0));
+    let approx = elems.member_approx(&of, 0.5, &env).unwrap();
+    assert_eq!(approx.data.as_slice(), [0, 1]);
+}
+
 impl<T: ArrayValue> Array<T> {
     /// Get the `index of` the rows of this array in another
+    ///
+    /// Rows that are not found get the row count of the searched array.
+    /// This is never a valid index, so when searching an empty array,
+    /// every row gets `0`.
+    /// NaN is equal to NaN unless [`Uiua::nan_never_matches`] is set.
     pub fn index_of(&self, searched_in: &Array<T>, env: &Uiua) -> UiuaResult<Array<f64>> {
+        self.index_of_impl(searched_in, None, env)
+    }
+    /// Get the `index of` the rows of this array in another,
+    /// using `not_found` for rows that are not found
+    pub fn index_of_or(
+        &self,
+        searched_in: &Array<T>,
+        not_found: f64,
+        env: &Uiua,
+    ) -> UiuaResult<Array<f64>> {
+        self.index_of_impl(searched_in, Some(not_found), env)
+    }
+    fn index_of_impl(
+        &self,
+        searched_in: &Array<T>,
+        not_found: Option<f64>,
+        env: &Uiua,
+    ) -> UiuaResult<Array<f64>> {
         let searched_for = self;
+        let not_found_value = not_found.unwrap_or(searched_in.row_count() as f64);
         Ok(match searched_for.rank().cmp(&searched_in.rank()) {
+            // Nothing can be found in an empty array
+            Ordering::Equal if searched_in.row_count() == 0 => {
+                let shape: Shape = self.shape.iter().cloned().take(1).collect();
+                Array::new(shape, cowslice![not_found_value; searched_for.row_count()])
+            }
             Ordering::Equal => {
                 let mut result_data = EcoVec::with_capacity(searched_for.row_count());
                 let mut members = HashMap::with_capacity(searched_in.row_count());
@@ -1122,11 +3865,15 @@ impl<T: ArrayValue> Array<T> {
                     members.entry(ArrayCmpSlice(of)).or_insert(i);
                 }
                 for elem in searched_for.row_slices() {
+                    if nan_never_matches(elem, env) {
+                        result_data.push(not_found_value);
+                        continue;
+                    }
                     result_data.push(
                         members
                             .get(&ArrayCmpSlice(elem))
                             .map(|i| *i as f64)
-                            .unwrap_or(searched_in.row_count() as f64),
+                            .unwrap_or(not_found_value),
                     );
                 }
                 let shape: Shape = self.shape.iter().cloned().take(1).collect();
@@ -1137,21 +3884,21 @@ impl<T: ArrayValue> Array<T> {
             Ordering::Greater => {
                 let mut rows = Vec::with_capacity(searched_for.row_count());
                 for elem in searched_for.rows() {
-                    rows.push(elem.index_of(searched_in, env)?);
+                    rows.push(elem.index_of_impl(searched_in, not_found, env)?);
                 }
                 Array::from_row_arrays(rows, env)?
             }
             Ordering::Less => {
                 if searched_in.rank() - searched_for.rank() == 1 {
-                    if searched_for.rank() == 0 {
+                    if nan_never_matches(&searched_for.data, env) {
+                        Array::from(not_found_value)
+                    } else if searched_for.rank() == 0 {
                         let searched_for = &searched_for.data[0];
                         Array::from(
                             searched_in
                                 .data
-                                .iter()
                                 .position(|of| searched_for.array_eq(of))
-                                .unwrap_or(searched_in.row_count())
-                                as f64,
+                                .map_or(not_found_value, |i| i as f64),
                         )
                     } else {
                         (searched_in
@@ -1160,13 +3907,13 @@ impl<T: ArrayValue> Array<T> {
                                 r.len() == searched_for.data.len()
                                     && r.iter().zip(&searched_for.data).all(|(a, b)| a.array_eq(b))
                             })
-                            .unwrap_or(searched_in.row_count()) as f64)
-                            .into()
+                            .map_or(not_found_value, |i| i as f64))
+                        .into()
                     }
                 } else {
                     let mut rows = Vec::with_capacity(searched_in.row_count());
                     for of in searched_in.rows() {
-                        rows.push(searched_for.index_of(&of, env)?);
+                        rows.push(searched_for.index_of_impl(&of, not_found, env)?);
                     }
                     Array::from_row_arrays(rows, env)?
                 }
@@ -1178,6 +3925,14 @@ impl<T: ArrayValue> Array<T> {
         let searched_for = self;
         Ok(match searched_for.rank().cmp(&searched_in.rank()) {
             Ordering::Equal => {
+                // If no row is looked for twice, no index is ever used up,
+                // so the result is the same as `index of`
+                if !env.rt.nan_never_matches {
+                    let mut distinct = HashSet::with_capacity(searched_for.row_count());
+                    if (searched_for.row_slices()).all(|row| distinct.insert(ArrayCmpSlice(row))) {
+                        return searched_for.index_of_impl(searched_in, None, env);
+                    }
+                }
                 let mut used = HashSet::new();
                 let mut result_data = EcoVec::with_capacity(searched_for.row_count());
                 if searched_for.rank() == 1 {
@@ -1229,7 +3984,6 @@ impl<T: ArrayValue> Array<T> {
                         Array::from(
                             searched_in
                                 .data
-                                .iter()
                                 .position(|of| searched_for.array_eq(of))
                                 .unwrap_or(searched_in.row_count())
                                 as f64,
@@ -1,13 +1,17 @@
 use std::{
+    cell::{Cell, RefCell},
     cmp::Ordering,
     iter::{once, repeat},
     mem::take,
 };
 
+use ecow::EcoVec;
+
+use super::{check_depth, checked_element_count, combine::shape_strides};
 use crate::{
-    algorithm::{op_bytes_ref_retry_fill, op_bytes_retry_fill, FillContext},
+    algorithm::{for_each_chunk, op_bytes_ref_retry_fill, op_bytes_retry_fill, FillContext},
     cowslice::{cowslice, CowSlice},
-    Array, ArrayValue, FormatShape, Shape, Uiua, UiuaResult, Value,
+    Array, ArrayValue, FormatShape, Shape, Uiua, UiuaError, UiuaResult, Value,
 };
 
 impl Value {
@@ -16,11 +20,18 @@ impl Value {
             Value::Num(arr) => {
                 let mut index_data = Vec::with_capacity(arr.element_count());
                 for &n in &arr.data {
-                    if n.fract() != 0.0 {
+                    // NaN and infinities are caught here too
+                    if n.fract() != 0.0 || n.is_nan() {
                         return Err(env.error(format!(
                             "Index must be an array of integers, but {n} is not an integer"
                         )));
                     }
+                    // `isize::MAX as f64` rounds up, so it is out of range itself
+                    if !(isize::MIN as f64..isize::MAX as f64).contains(&n) {
+                        return Err(
+                            env.error(format!("Index {n} is too large to be used as an index"))
+                        );
+                    }
                     index_data.push(n as isize);
                 }
                 (&arr.shape, index_data)
@@ -44,6 +55,7 @@ impl Value {
     /// Use this array as an index to pick from another
     pub fn pick(self, from: Self, env: &Uiua) -> UiuaResult<Self> {
         let (index_shape, index_data) = self.as_shaped_indices(env)?;
+        check_depth(index_shape.len(), "Pick", env)?;
         Ok(match from {
             Value::Num(a) => Value::Num(a.pick(index_shape, &index_data, env)?),
             #[cfg(feature = "bytes")]
@@ -57,6 +69,54 @@ impl Value {
             Value::Box(a) => Value::Box(a.pick(index_shape, &index_data, env)?),
         })
     }
+    /// Use this array as an index to pick from another, filling where `mask` is `0`
+    ///
+    /// `mask` has one entry per index. Masked indices are never looked up,
+    /// so they may be out of bounds. A fill value must be set.
+    pub fn pick_masked(self, from: Self, mask: &[u8], env: &Uiua) -> UiuaResult<Self> {
+        let (index_shape, index_data) = self.as_shaped_indices(env)?;
+        Ok(match from {
+            Value::Num(a) => Value::Num(a.pick_masked(index_shape, &index_data, mask, env)?),
+            #[cfg(feature = "bytes")]
+            Value::Byte(a) => op_bytes_retry_fill(
+                a,
+                |a| Ok(a.pick_masked(index_shape, &index_data, mask, env)?.into()),
+                |a| Ok(a.pick_masked(index_shape, &index_data, mask, env)?.into()),
+            )?,
+            Value::Complex(a) => {
+                Value::Complex(a.pick_masked(index_shape, &index_data, mask, env)?)
+            }
+            Value::Char(a) => Value::Char(a.pick_masked(index_shape, &index_data, mask, env)?),
+            Value::Box(a) => Value::Box(a.pick_masked(index_shape, &index_data, mask, env)?),
+        })
+    }
+    /// Use the sub-arrays of this value at some depth to `pick` from
+    /// the sub-arrays of another value at some depth
+    ///
+    /// Each index sub-array must be a scalar or a list, and picks from
+    /// its matching sub-array. The axes it indexes are removed, so a
+    /// scalar index picks a row of each sub-array.
+    pub fn pick_depth(
+        &self,
+        from: Self,
+        a_depth: usize,
+        b_depth: usize,
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        let index = self.as_integer_array(env, "Index must be an array of integers")?;
+        Ok(match from {
+            Value::Num(a) => a.pick_depth(index, b_depth, a_depth, env)?.into(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(a) => op_bytes_retry_fill(
+                a,
+                |a| Ok(a.pick_depth(index.clone(), b_depth, a_depth, env)?.into()),
+                |a| Ok(a.pick_depth(index.clone(), b_depth, a_depth, env)?.into()),
+            )?,
+            Value::Complex(a) => a.pick_depth(index, b_depth, a_depth, env)?.into(),
+            Value::Char(a) => a.pick_depth(index, b_depth, a_depth, env)?.into(),
+            Value::Box(a) => a.pick_depth(index, b_depth, a_depth, env)?.into(),
+        })
+    }
     pub(crate) fn unpick(self, index: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
         let (index_shape, index_data) = index.as_shaped_indices(env)?;
         if index_shape.len() > 1 {
@@ -126,6 +186,53 @@ impl<T: ArrayValue> Array<T> {
         new_shape.extend_from_slice(&self.shape[*index_shape.last().unwrap()..]);
         Ok(Array::new(new_shape, new_data))
     }
+    /// `pick` with a mask of which indices are valid
+    ///
+    /// Where the mask is `0`, the fill value is used instead of the index.
+    pub fn pick_masked(
+        &self,
+        index_shape: &[usize],
+        index_data: &[isize],
+        mask: &[u8],
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        let fill = env.fill::<T>().map_err(|e| {
+            env.error(format!("Cannot do a masked pick without a fill value{e}"))
+                .fill()
+        })?;
+        let (outer_shape, index_len) = if index_shape.len() <= 1 {
+            (&[][..], index_data.len())
+        } else {
+            let (last, outer) = index_shape.split_last().unwrap();
+            (outer, *last)
+        };
+        let index_count: usize = outer_shape.iter().product();
+        if mask.len() != index_count {
+            return Err(env.error(format!(
+                "Mask has {} entries, but there are {index_count} indices",
+                mask.len()
+            )));
+        }
+        if index_len > self.rank() {
+            return Err(env.error(format!(
+                "Cannot pick from rank {} array with index of length {index_len}",
+                self.rank()
+            )));
+        }
+        let row_len: usize = self.shape[index_len..].iter().product();
+        let mut new_data = CowSlice::with_capacity(index_count * row_len);
+        for (i, &valid) in mask.iter().enumerate() {
+            if valid == 0 {
+                new_data.extend((0..row_len).map(|_| fill.clone()));
+            } else {
+                let index = &index_data[i * index_len..][..index_len];
+                new_data.extend_from_slice(&self.pick_single(index, env)?.data);
+            }
+        }
+        let mut new_shape = Shape::from(outer_shape);
+        new_shape.extend_from_slice(&self.shape[index_len..]);
+        Ok(Array::new(new_shape, new_data))
+    }
     fn pick_single(&self, index: &[isize], env: &Uiua) -> UiuaResult<Self> {
         if index.len() > self.rank() {
             return Err(env.error(format!(
@@ -135,8 +242,9 @@ impl<T: ArrayValue> Array<T> {
             )));
         }
         let mut picked = self.data.clone();
+        let strides = shape_strides(&self.shape);
         for (d, (&s, &i)) in self.shape.iter().zip(index).enumerate() {
-            let row_len: usize = self.shape[d + 1..].iter().product();
+            let row_len = strides[d];
             let s = s as isize;
             if i >= s || i < -s {
                 match env.fill::<T>() {
@@ -162,6 +270,58 @@ impl<T: ArrayValue> Array<T> {
         let shape = Shape::from(&self.shape[index.len()..]);
         Ok(Array::new(shape, picked))
     }
+    /// `pick` from the sub-arrays of this array at some depth, using the
+    /// sub-arrays of an index array at some depth
+    pub fn pick_depth(
+        mut self,
+        index: Array<isize>,
+        depth: usize,
+        index_depth: usize,
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
+        let picked_rows: RefCell<Vec<Self>> = RefCell::new(Vec::new());
+        let sub_rank = Cell::new(None);
+        self.depth_slices(&index, depth, index_depth, env, |ash, a, bsh, b, env| {
+            sub_rank.set(Some(ash.len()));
+            if bsh.len() > 1 {
+                return Err(env.error(format!(
+                    "Cannot pick at depth with rank {} index",
+                    bsh.len()
+                )));
+            }
+            let sub = Array::new(Shape::from(ash), a.iter().cloned().collect::<CowSlice<_>>());
+            picked_rows.borrow_mut().push(sub.pick_single(b, env)?);
+            Ok(())
+        })?;
+        let picked_rows = picked_rows.into_inner();
+        let Some(sub_rank) = sub_rank.get() else {
+            // There are no sub-arrays, so only the indexed axes are removed
+            let depth = depth.min(self.rank());
+            let index_len = match &index.shape[index_depth.min(index.rank())..] {
+                [] => 1,
+                [n] => *n,
+                bsh => {
+                    return Err(env.error(format!(
+                        "Cannot pick at depth with rank {} index",
+                        bsh.len()
+                    )))
+                }
+            };
+            let mut shape = self.shape.clone();
+            shape.drain(depth..(depth + index_len).min(shape.len()));
+            return Ok(Array::new(shape, CowSlice::new()));
+        };
+        // Replace the sub-arrays' part of the shape with the picked shape
+        let mut shape = Shape::from(&self.shape[..self.rank() - sub_rank]);
+        shape.extend_from_slice(&picked_rows[0].shape);
+        let mut data = CowSlice::with_capacity(picked_rows[0].element_count() * picked_rows.len());
+        for row in &picked_rows {
+            data.extend_from_slice(&row.data);
+        }
+        let arr = Array::new(shape, data);
+        arr.validate_shape();
+        Ok(arr)
+    }
     fn unpick(
         self,
         index_shape: &[usize],
@@ -237,9 +397,16 @@ impl<T: ArrayValue> Array<T> {
 
 impl Value {
     /// Use this value to `take` from another
-    pub fn take(self, from: Self, env: &Uiua) -> UiuaResult<Self> {
+    ///
+    /// Taking from a scalar is an error unless scalar promotion is enabled
+    /// with [`Uiua::promote_scalars`], in which case the scalar is treated as
+    /// a single-element list.
+    pub fn take(self, mut from: Self, env: &Uiua) -> UiuaResult<Self> {
         if from.rank() == 0 {
-            return Err(env.error("Cannot take from scalar"));
+            if !env.rt.promote_scalars {
+                return Err(env.error("Cannot take from scalar"));
+            }
+            from.shape_mut().push(1);
         }
         let index = self.as_ints(env, "Index must be a list of integers")?;
         Ok(match from {
@@ -256,9 +423,16 @@ impl Value {
         })
     }
     /// Use this value to `drop` from another
-    pub fn drop(self, from: Self, env: &Uiua) -> UiuaResult<Self> {
+    ///
+    /// Dropping from a scalar is an error unless scalar promotion is enabled
+    /// with [`Uiua::promote_scalars`], in which case the scalar is treated as
+    /// a single-element list.
+    pub fn drop(self, mut from: Self, env: &Uiua) -> UiuaResult<Self> {
         if from.rank() == 0 {
-            return Err(env.error("Cannot drop from scalar"));
+            if !env.rt.promote_scalars {
+                return Err(env.error("Cannot drop from scalar"));
+            }
+            from.shape_mut().push(1);
         }
         let index = self.as_ints(env, "Index must be a list of integers")?;
         Ok(match from {
@@ -270,6 +444,32 @@ impl Value {
             Value::Box(a) => Value::Box(a.drop(&index, env)?),
         })
     }
+    /// `take` from this value along a single axis, leaving the others whole
+    pub fn take_axis(self, amount: isize, axis: usize, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match self {
+            Value::Num(a) => a.take_axis(amount, axis, env)?.into(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(a) => op_bytes_retry_fill(
+                a,
+                |a| Ok(a.take_axis(amount, axis, env)?.into()),
+                |a| Ok(a.take_axis(amount, axis, env)?.into()),
+            )?,
+            Value::Complex(a) => a.take_axis(amount, axis, env)?.into(),
+            Value::Char(a) => a.take_axis(amount, axis, env)?.into(),
+            Value::Box(a) => a.take_axis(amount, axis, env)?.into(),
+        })
+    }
+    /// `drop` from this value along a single axis, leaving the others whole
+    pub fn drop_axis(self, amount: isize, axis: usize, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match self {
+            Value::Num(a) => a.drop_axis(amount, axis, env)?.into(),
+            #[cfg(feature = "bytes")]
+            Value::Byte(a) => a.drop_axis(amount, axis, env)?.into(),
+            Value::Complex(a) => a.drop_axis(amount, axis, env)?.into(),
+            Value::Char(a) => a.drop_axis(amount, axis, env)?.into(),
+            Value::Box(a) => a.drop_axis(amount, axis, env)?.into(),
+        })
+    }
     pub(crate) fn untake(self, index: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
         let index = index.as_ints(env, "Index must be a list of integers")?;
         self.generic_bin_into(
@@ -308,9 +508,36 @@ impl Value {
     }
 }
 
+fn take_fill_error(
+    taking: usize,
+    row_count: usize,
+    axis: Option<usize>,
+    e: &str,
+    env: &Uiua,
+) -> UiuaError {
+    let axis = axis
+        .map(|axis| format!(" at axis {axis}"))
+        .unwrap_or_default();
+    env.error(format!(
+        "Cannot take {taking} rows from array with {row_count} row{}{axis} \
+        outside a fill context{e}",
+        if row_count == 1 { "" } else { "s" }
+    ))
+    .fill()
+}
+
 impl<T: ArrayValue> Array<T> {
     /// `take` from this array
-    pub fn take(mut self, index: &[isize], env: &Uiua) -> UiuaResult<Self> {
+    pub fn take(self, index: &[isize], env: &Uiua) -> UiuaResult<Self> {
+        check_depth(index.len(), "Take", env)?;
+        self.take_impl(index, (index.len() > 1).then_some(0), env)
+    }
+    /// `take` from this array, where `axis` is the axis of the original
+    /// array that the first element of `index` applies to
+    ///
+    /// `axis` is only used in error messages, and is `None`
+    /// when taking along a single axis.
+    fn take_impl(mut self, index: &[isize], axis: Option<usize>, env: &Uiua) -> UiuaResult<Self> {
         Ok(match index {
             [] => self,
             &[taking] => {
@@ -329,15 +556,9 @@ impl<T: ArrayValue> Array<T> {
                                     );
                                 }
                                 Err(e) => {
-                                    return Err(env
-                                        .error(format!(
-                                            "Cannot take {} rows from array with {} row{} \
-                                            outside a fill context{e}",
-                                            abs_taking,
-                                            row_count,
-                                            if row_count == 1 { "" } else { "s" }
-                                        ))
-                                        .fill());
+                                    return Err(take_fill_error(
+                                        abs_taking, row_count, axis, e, env,
+                                    ));
                                 }
                             }
                         } else {
@@ -354,15 +575,9 @@ impl<T: ArrayValue> Array<T> {
                                         .collect()
                                 }
                                 Err(e) => {
-                                    return Err(env
-                                        .error(format!(
-                                            "Cannot take {} rows from array with {} row{} \
-                                            outside a fill context{e}",
-                                            abs_taking,
-                                            row_count,
-                                            if row_count == 1 { "" } else { "s" }
-                                        ))
-                                        .fill());
+                                    return Err(take_fill_error(
+                                        abs_taking, row_count, axis, e, env,
+                                    ));
                                 }
                             }
                         } else {
@@ -400,15 +615,42 @@ impl<T: ArrayValue> Array<T> {
                     .zip(&self.shape[1..])
                     .all(|(&i, &s)| i.unsigned_abs() == s)
                 {
-                    return self.take(&[taking], env);
+                    return self.take_impl(&[taking], axis, env);
                 }
+                // Taking a prefix along every axis can be done without recursing
+                if (index.iter().zip(&self.shape)).all(|(&i, &s)| i >= 0 && i as usize <= s) {
+                    let prefix: Vec<usize> = index.iter().map(|&i| i as usize).collect();
+                    return Ok(self.take_prefix(&prefix));
+                }
+                // The shape to use if no rows are taken
+                let mut empty_shape = self.shape.clone();
+                empty_shape[0] = 0;
+                let has_fill = T::get_fill(env).is_ok();
+                for (s, &i) in empty_shape[1..].iter_mut().zip(sub_index) {
+                    *s = if has_fill {
+                        i.unsigned_abs()
+                    } else {
+                        (*s).min(i.unsigned_abs())
+                    };
+                }
+                let from_rows = |rows: Vec<Self>| {
+                    if rows.is_empty() {
+                        Array::new(empty_shape.clone(), CowSlice::new())
+                    } else {
+                        Array::from_row_arrays_infallible(rows)
+                    }
+                };
                 let mut new_rows = Vec::with_capacity(abs_taking);
                 let mut arr = if taking >= 0 {
                     // Take in each row
                     for row in self.rows().take(abs_taking) {
-                        new_rows.push(row.take(sub_index, env)?);
+                        new_rows.push(row.take_impl(
+                            sub_index,
+                            Some(axis.unwrap_or(0) + 1),
+                            env,
+                        )?);
                     }
-                    let mut arr = Array::from_row_arrays_infallible(new_rows);
+                    let mut arr = from_rows(new_rows);
                     // Extend with fill values if necessary
                     if abs_taking > arr.row_count() {
                         match T::get_fill(env) {
@@ -419,15 +661,13 @@ impl<T: ArrayValue> Array<T> {
                                 );
                             }
                             Err(e) => {
-                                return Err(env
-                                    .error(format!(
-                                        "Cannot take {} rows from array with {} row{} \
-                                        outside a fill context{e}",
-                                        abs_taking,
-                                        arr.row_count(),
-                                        if arr.row_count() == 1 { "" } else { "s" }
-                                    ))
-                                    .fill());
+                                return Err(take_fill_error(
+                                    abs_taking,
+                                    arr.row_count(),
+                                    axis,
+                                    e,
+                                    env,
+                                ));
                             }
                         }
                     }
@@ -436,9 +676,13 @@ impl<T: ArrayValue> Array<T> {
                     // Take in each row
                     let start = self.row_count().saturating_sub(abs_taking);
                     for row in self.rows().skip(start) {
-                        new_rows.push(row.take(sub_index, env)?);
+                        new_rows.push(row.take_impl(
+                            sub_index,
+                            Some(axis.unwrap_or(0) + 1),
+                            env,
+                        )?);
                     }
-                    let mut arr = Array::from_row_arrays_infallible(new_rows);
+                    let mut arr = from_rows(new_rows);
                     // Prepend with fill values if necessary
                     if abs_taking > arr.row_count() {
                         match T::get_fill(env) {
@@ -450,15 +694,13 @@ impl<T: ArrayValue> Array<T> {
                                     .collect();
                             }
                             Err(e) => {
-                                return Err(env
-                                    .error(format!(
-                                        "Cannot take {} rows from array with {} row{} \
-                                        outside a fill context{e}",
-                                        abs_taking,
-                                        arr.row_count(),
-                                        if arr.row_count() == 1 { "" } else { "s" }
-                                    ))
-                                    .fill());
+                                return Err(take_fill_error(
+                                    abs_taking,
+                                    arr.row_count(),
+                                    axis,
+                                    e,
+                                    env,
+                                ));
                             }
                         }
                     }
@@ -470,8 +712,41 @@ impl<T: ArrayValue> Array<T> {
             }
         })
     }
+    /// `take` an in-bounds prefix along multiple axes with a single strided copy
+    fn take_prefix(&self, index: &[usize]) -> Self {
+        let (outer_shape, last) = index.split_at(index.len() - 1);
+        let inner_len: usize = self.shape[index.len()..].iter().product();
+        let block_len = last[0] * inner_len;
+        let mut strides = vec![0; outer_shape.len()];
+        let mut stride = inner_len * self.shape[index.len() - 1];
+        for (s, dim) in strides.iter_mut().zip(&self.shape).rev() {
+            *s = stride;
+            stride *= dim;
+        }
+        let outer_count: usize = outer_shape.iter().product();
+        let mut data = EcoVec::with_capacity(outer_count * block_len);
+        let mut outer = vec![0; outer_shape.len()];
+        for _ in 0..outer_count {
+            let start: usize = outer.iter().zip(&strides).map(|(i, s)| i * s).sum();
+            data.extend_from_slice(&self.data[start..start + block_len]);
+            for (i, dim) in outer.iter_mut().zip(outer_shape).rev() {
+                *i += 1;
+                if *i < *dim {
+                    break;
+                }
+                *i = 0;
+            }
+        }
+        let shape: Shape = (index.iter().chain(&self.shape[index.len()..]))
+            .copied()
+            .collect();
+        let arr = Array::new(shape, data);
+        arr.validate_shape();
+        arr
+    }
     /// `drop` from this array
     pub fn drop(mut self, index: &[isize], env: &Uiua) -> UiuaResult<Self> {
+        check_depth(index.len(), "Drop", env)?;
         Ok(match index {
             [] => self,
             &[dropping] => {
@@ -519,10 +794,57 @@ impl<T: ArrayValue> Array<T> {
                         new_rows.push(row.drop(sub_index, env)?);
                     }
                 };
+                if new_rows.is_empty() {
+                    // Keep the shape of the rows even if there are none
+                    let mut shape = self.shape.clone();
+                    shape[0] = 0;
+                    for (s, &i) in shape[1..].iter_mut().zip(sub_index) {
+                        *s = s.saturating_sub(i.unsigned_abs());
+                    }
+                    return Ok(Array::new(shape, CowSlice::new()));
+                }
                 Array::from_row_arrays(new_rows, env)?
             }
         })
     }
+    /// `take` from this array along a single axis, leaving the others whole
+    ///
+    /// This is the same as taking with an index that uses the full length
+    /// of every axis before `axis`.
+    pub fn take_axis(self, amount: isize, axis: usize, env: &Uiua) -> UiuaResult<Self> {
+        self.on_axis("take", axis, env, |arr| arr.take(&[amount], env))
+    }
+    /// `drop` from this array along a single axis, leaving the others whole
+    ///
+    /// This is the same as dropping with an index that is `0`
+    /// for every axis before `axis`.
+    pub fn drop_axis(self, amount: isize, axis: usize, env: &Uiua) -> UiuaResult<Self> {
+        self.on_axis("drop", axis, env, |arr| arr.drop(&[amount], env))
+    }
+    /// Apply an operation on the leading axis to some other axis
+    /// by moving that axis to the front and back again
+    fn on_axis(
+        mut self,
+        name: &str,
+        axis: usize,
+        env: &Uiua,
+        f: impl FnOnce(Self) -> UiuaResult<Self>,
+    ) -> UiuaResult<Self> {
+        if axis >= self.rank() {
+            return Err(env.error(format!(
+                "Cannot {name} from axis {axis} of rank {} array",
+                self.rank()
+            )));
+        }
+        for _ in 0..axis {
+            self.transpose();
+        }
+        let mut arr = f(self)?;
+        for _ in 0..axis {
+            arr.inv_transpose();
+        }
+        Ok(arr)
+    }
     fn untake(self, index: &[isize], into: Self, env: &Uiua) -> UiuaResult<Self> {
         self.untake_impl("take", "taken", index, into, env)
     }
@@ -574,6 +896,9 @@ impl<T: ArrayValue> Array<T> {
                     )));
                 }
                 let into_row_count = into.row_count();
+                if into_row_count == 0 {
+                    return Ok(into);
+                }
                 let mut new_rows = Vec::with_capacity(into_row_count);
                 if untaking >= 0 {
                     for (from, into) in from.rows().zip(into.rows()) {
@@ -592,6 +917,9 @@ impl<T: ArrayValue> Array<T> {
         })
     }
     fn undrop(self, index: &[isize], into: Self, env: &Uiua) -> UiuaResult<Self> {
+        if (index.iter().zip(&into.shape)).any(|(&i, &s)| i.unsigned_abs() > s) {
+            return Err(env.error("Cannot undo drop that removed more rows than existed"));
+        }
         let index: Vec<isize> = index
             .iter()
             .zip(&into.shape)
@@ -607,21 +935,63 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+/// How `select` treats indices that are out of bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OobMode {
+    /// Always error, ignoring any fill value
+    Error,
+    /// Select the fill value if one is set, and error otherwise
+    ///
+    /// This is what [`Value::select`] does.
+    #[default]
+    Fill,
+    /// Select the nearest row, so negative indices past the start
+    /// select the first row and indices past the end select the last
+    Clamp,
+    /// Wrap indices around, so the index is taken modulo the row count
+    Wrap,
+}
+
 impl Value {
     /// Use this value to `select` from another
+    ///
+    /// Out-of-bounds indices select the fill value if one is set.
+    /// Use [`Value::select_with_mode`] to handle them differently.
     pub fn select(&self, from: &Self, env: &Uiua) -> UiuaResult<Self> {
+        self.select_with_mode(from, OobMode::Fill, env)
+    }
+    /// Use this value to `select` from another, treating out-of-bounds indices according to `mode`
+    ///
+    /// [`OobMode::Clamp`] and [`OobMode::Wrap`] still error if there
+    /// are no rows to select from.
+    pub fn select_with_mode(&self, from: &Self, mode: OobMode, env: &Uiua) -> UiuaResult<Self> {
         let (indices_shape, indices_data) = self.as_shaped_indices(env)?;
+        check_depth(indices_shape.len(), "Select", env)?;
         Ok(match from {
-            Value::Num(a) => a.select_impl(indices_shape, &indices_data, env)?.into(),
+            Value::Num(a) => a
+                .select_impl(indices_shape, &indices_data, mode, env)?
+                .into(),
             #[cfg(feature = "bytes")]
             Value::Byte(a) => op_bytes_ref_retry_fill(
                 a,
-                |a| Ok(a.select_impl(indices_shape, &indices_data, env)?.into()),
-                |a| Ok(a.select_impl(indices_shape, &indices_data, env)?.into()),
+                |a| {
+                    Ok(a.select_impl(indices_shape, &indices_data, mode, env)?
+                        .into())
+                },
+                |a| {
+                    Ok(a.select_impl(indices_shape, &indices_data, mode, env)?
+                        .into())
+                },
             )?,
-            Value::Complex(a) => a.select_impl(indices_shape, &indices_data, env)?.into(),
-            Value::Char(a) => a.select_impl(indices_shape, &indices_data, env)?.into(),
-            Value::Box(a) => a.select_impl(indices_shape, &indices_data, env)?.into(),
+            Value::Complex(a) => a
+                .select_impl(indices_shape, &indices_data, mode, env)?
+                .into(),
+            Value::Char(a) => a
+                .select_impl(indices_shape, &indices_data, mode, env)?
+                .into(),
+            Value::Box(a) => a
+                .select_impl(indices_shape, &indices_data, mode, env)?
+                .into(),
         })
     }
     pub(crate) fn unselect(self, index: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
@@ -654,7 +1024,7 @@ impl Value {
             |a, b| a.unselect_impl(ind_shape, &ind, b, env).map(Into::into),
             |a, b| {
                 env.error(format!(
-                    "Cannot untake {} into {}",
+                    "Cannot unselect {} into {}",
                     a.type_name(),
                     b.type_name()
                 ))
@@ -668,6 +1038,7 @@ impl<T: ArrayValue> Array<T> {
         &self,
         indices_shape: &[usize],
         indices: &[isize],
+        mode: OobMode,
         env: &Uiua,
     ) -> UiuaResult<Self> {
         if indices_shape.len() > 1 {
@@ -683,11 +1054,11 @@ impl<T: ArrayValue> Array<T> {
             }
             let mut rows = Vec::with_capacity(row_count);
             for indices_row in indices.chunks_exact(row_len) {
-                rows.push(self.select_impl(&indices_shape[1..], indices_row, env)?);
+                rows.push(self.select_impl(&indices_shape[1..], indices_row, mode, env)?);
             }
             Array::from_row_arrays(rows, env)
         } else {
-            let mut res = self.select(indices, env)?;
+            let mut res = self.select(indices, mode, env)?;
             if indices_shape.is_empty() {
                 res.shape.remove(0);
             }
@@ -707,53 +1078,93 @@ impl<T: ArrayValue> Array<T> {
             self.unselect(indices_shape, indices, into, env)
         }
     }
-    fn select(&self, indices: &[isize], env: &Uiua) -> UiuaResult<Self> {
-        let mut selected = CowSlice::with_capacity(self.row_len() * indices.len());
+    fn select(&self, indices: &[isize], mode: OobMode, env: &Uiua) -> UiuaResult<Self> {
+        // Selecting every row in order is just a copy, so share the data
+        if self.rank() > 0
+            && indices.len() == self.row_count()
+            && (indices.iter().enumerate()).all(|(i, &j)| j == i as isize)
+        {
+            return Ok(Array::new(self.shape.clone(), self.data.clone()));
+        }
         let row_len = self.row_len();
         let row_count = self.row_count();
+        // Resolve the indices, with `None` for rows that are filled
+        let mut fill = None;
+        let mut rows = Vec::with_capacity(indices.len());
         for &i in indices {
-            let i = if i >= 0 {
-                let ui = i as usize;
-                if ui >= row_count {
-                    match env.fill::<T>() {
-                        Ok(fill) => {
-                            selected.extend(repeat(fill).take(row_len));
-                            continue;
-                        }
-                        Err(e) => {
-                            return Err(env
-                                .error(format!(
-                                    "Index {} is out of bounds of length {}{e}",
-                                    i, row_count
-                                ))
-                                .fill());
-                        }
+            let ui = if i >= 0 {
+                i as usize
+            } else {
+                (row_count as isize + i) as usize
+            };
+            if ui < row_count {
+                rows.push(Some(ui));
+                continue;
+            }
+            match mode {
+                OobMode::Clamp if row_count > 0 => {
+                    rows.push(Some(if i < 0 { 0 } else { row_count - 1 }));
+                    continue;
+                }
+                OobMode::Wrap if row_count > 0 => {
+                    rows.push(Some(i.rem_euclid(row_count as isize) as usize));
+                    continue;
+                }
+                OobMode::Fill => {}
+                _ => return Err(env.error(out_of_bounds_message(i, row_count))),
+            }
+            if fill.is_none() {
+                match env.fill::<T>() {
+                    Ok(f) => fill = Some(f),
+                    Err(e) => {
+                        return Err(env
+                            .error(format!("{}{e}", out_of_bounds_message(i, row_count)))
+                            .fill());
                     }
                 }
-                ui
-            } else {
-                let pos_i = (row_count as isize + i) as usize;
-                if pos_i >= row_count {
-                    match env.fill::<T>() {
-                        Ok(fill) => {
-                            selected.extend(repeat(fill).take(row_len));
-                            continue;
-                        }
-                        Err(e) => {
-                            return Err(env
-                                .error(format!(
-                                    "Index {} is out of bounds of length {}{e}",
-                                    i, row_count
-                                ))
-                                .fill());
+            }
+            rows.push(None);
+        }
+        let row_shape = self.shape.iter().skip(1).copied();
+        checked_element_count::<T>(once(indices.len()).chain(row_shape), "Select", env)?;
+        // Each run of equal indices writes to its own section of the output
+        let mut selected = EcoVec::new();
+        let init = fill.clone().or_else(|| self.data.first().cloned());
+        if let Some(init) = init.filter(|_| row_len > 0) {
+            selected = EcoVec::from_elem(init, indices.len() * row_len);
+            let mut runs = Vec::new();
+            let mut rest = selected.make_mut();
+            let mut rows = rows.as_slice();
+            while let Some(&row) = rows.first() {
+                let run_len = rows.iter().take_while(|&&r| r == row).count();
+                let (dst, r) = take(&mut rest).split_at_mut(run_len * row_len);
+                runs.push((dst, row));
+                rest = r;
+                rows = &rows[run_len..];
+            }
+            let op = |_: &mut (), _, run: &mut [(&mut [T], Option<usize>)]| {
+                let (dst, row) = &mut run[0];
+                match *row {
+                    Some(i) => {
+                        dst[..row_len].clone_from_slice(&self.data[i * row_len..(i + 1) * row_len])
+                    }
+                    None => {
+                        if let Some(fill) = &fill {
+                            dst[..row_len].fill(fill.clone())
                         }
                     }
                 }
-                pos_i
+                // Repeat the first row by doubling the filled section
+                let mut filled = row_len;
+                while filled < dst.len() {
+                    let (src, unfilled) = dst.split_at_mut(filled);
+                    let n = filled.min(unfilled.len());
+                    unfilled[..n].clone_from_slice(&src[..n]);
+                    filled += n;
+                }
             };
-            let start = i * row_len;
-            let end = start + row_len;
-            selected.extend_from_slice(&self.data[start..end]);
+            let parallel = runs.len() > 500;
+            for_each_chunk(&mut runs, 1, parallel, || (), op);
         }
         let mut shape = self.shape.clone();
         if let Some(s) = shape.get_mut(0) {
@@ -788,6 +1199,20 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+/// Describe an out-of-bounds row index
+///
+/// Negative indices also show the position they count back to
+fn out_of_bounds_message(index: isize, len: usize) -> String {
+    if index < 0 {
+        format!(
+            "Index {index} (normalized to {}) is out of bounds of length {len}",
+            len as isize + index
+        )
+    } else {
+        format!("Index {index} is out of bounds of length {len}")
+    }
+}
+
 fn unselect_inner<'a, T: ArrayValue>(
     row_slices: impl Iterator<Item = &'a [T]>,
     indices: &[isize],
@@ -802,10 +1227,7 @@ fn unselect_inner<'a, T: ArrayValue>(
             let uns_index = index as usize;
             if uns_index >= into_row_count {
                 return Err(env
-                    .error(format!(
-                        "Index {} is out of bounds of length {}",
-                        index, into_row_count
-                    ))
+                    .error(out_of_bounds_message(index, into_row_count))
                     .fill());
             }
             uns_index
@@ -813,10 +1235,7 @@ fn unselect_inner<'a, T: ArrayValue>(
             let pos_i = (into_row_count as isize + index) as usize;
             if pos_i >= into_row_count {
                 return Err(env
-                    .error(format!(
-                        "Index {} is out of bounds of length {}",
-                        index, into_row_count
-                    ))
+                    .error(out_of_bounds_message(index, into_row_count))
                     .fill());
             }
             pos_i
@@ -829,3 +1248,644 @@ fn unselect_inner<'a, T: ArrayValue>(
     }
     Ok(())
 }
+
+#[test]
+fn take_drop_axis() {
+    let mut env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![2, 3, 4],
+        (0..24).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    // Each axis agrees with taking and dropping with a full index
+    for axis in 0..3 {
+        for amount in [-3, -1, 0, 1, 2] {
+            let mut take_index: Vec<isize> =
+                (arr.shape[..axis].iter()).map(|&d| d as isize).collect();
+            take_index.push(amount);
+            let mut drop_index = vec![0; axis];
+            drop_index.push(amount);
+            let taken = arr.clone().take_axis(amount, axis, &env);
+            let expected = arr.clone().take(&take_index, &env);
+            assert_eq!(taken.is_ok(), expected.is_ok(), "{axis} {amount}");
+            if let (Ok(taken), Ok(expected)) = (taken, expected) {
+                assert_eq!(taken, expected, "take {amount} from axis {axis}");
+            }
+            let dropped = arr.clone().drop_axis(amount, axis, &env).unwrap();
+            let expected = arr.clone().drop(&drop_index, &env).unwrap();
+            assert_eq!(dropped, expected, "drop {amount} from axis {axis}");
+        }
+    }
+    let taken = arr.clone().take_axis(-2, 2, &env).unwrap();
+    assert_eq!(&*taken.shape, [2, 3, 2]);
+    assert_eq!(taken.data[..4], [2.0, 3.0, 6.0, 7.0]);
+    // Fills apply along the chosen axis
+    env.with_fill(Value::from(0.0), |env| {
+        let filled = arr.clone().take_axis(5, 1, env)?;
+        assert_eq!(&*filled.shape, [2, 5, 4]);
+        assert_eq!(filled.data[12..20], [0.0; 8]);
+        Ok(())
+    })
+    .unwrap();
+    assert!(arr.clone().take_axis(1, 3, &env).is_err());
+    assert!(Value::from(1.0).drop_axis(1, 0, &env).is_err());
+}
+
+#[test]
+#[cfg(feature = "bytes")]
+fn unselect_mixed_numbers() {
+    let env = Uiua::with_native_sys();
+    let index: Value = Array::from_iter([2.0, 0.0]).into();
+    // Numbers into bytes
+    let selected: Value = Array::from_iter([0.5, 7.0]).into();
+    let into: Value = Array::<u8>::from_iter([1, 2, 3]).into();
+    let res = selected.unselect(index.clone(), into, &env).unwrap();
+    assert_eq!(res, Array::from_iter([7.0, 2.0, 0.5]).into());
+    // Bytes into numbers
+    let selected: Value = Array::<u8>::from_iter([9, 8]).into();
+    let into: Value = Array::from_iter([1.5, 2.5, 3.5]).into();
+    let res = selected.unselect(index.clone(), into, &env).unwrap();
+    assert_eq!(res, Array::from_iter([8.0, 2.5, 9.0]).into());
+    // Other mixes are still errors
+    let selected: Value = Array::from_iter([1.0, 2.0]).into();
+    let into = Value::from("abc".to_string());
+    let err = selected.unselect(index, into, &env).unwrap_err();
+    assert!(err.to_string().contains("Cannot unselect"), "{err}");
+}
+
+#[test]
+fn depth_limit() {
+    let too_deep = |env: &mut Uiua, code: &str| {
+        let Err(err) = env.run_str(code) else {
+            panic!("{code} should have errored");
+        };
+        let err = err.to_string();
+        assert!(err.contains("maximum array nesting depth"), "{code}: {err}");
+    };
+    let mut env = Uiua::with_native_sys();
+    // Deeper than the default limit errors instead of overflowing the stack
+    too_deep(&mut env, "↙ ⊂ ↯299 ¯1 0 ↯ ↯300 1 5");
+    too_deep(&mut env, "↘ ↯300 0 ↯ ↯300 1 5");
+    assert!(env.run_str("↙ ⊂ ↯199 ¯1 0 ↯ ↯200 1 5").is_ok());
+    // The limit is configurable
+    let mut env = Uiua::with_native_sys().with_depth_limit(2);
+    assert!(env.run_str("↙ 1_1 ↯2_2 0").is_ok());
+    assert!(env.run_str("⊏ [[0]] [1 2]").is_ok());
+    too_deep(&mut env, "↙ 1_1_1 ↯2_2_2 0");
+    too_deep(&mut env, "⊡ [[[0]]] [1]");
+    too_deep(&mut env, "⊏ [[[0]]] [1]");
+    too_deep(&mut env, "∊ ↯2_2_2 0 1");
+}
+
+#[test]
+fn take_error_names_axis() {
+    let env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(tinyvec::tiny_vec![3, 3], CowSlice::from_elem(0.0, 9));
+    let take_err = |index: &[isize]| arr.clone().take(index, &env).unwrap_err().to_string();
+    // A single axis is not named
+    let err = take_err(&[5]);
+    assert!(
+        err.contains("5 rows from array with 3 rows outside"),
+        "{err}"
+    );
+    // Otherwise, the failing axis is
+    let err = take_err(&[5, 3]);
+    assert!(err.contains("with 3 rows at axis 0 outside"), "{err}");
+    let err = take_err(&[2, -5]);
+    assert!(err.contains("with 3 rows at axis 1 outside"), "{err}");
+}
+
+#[test]
+fn huge_float_indices() {
+    let mut env = Uiua::with_native_sys();
+    let shaped_indices =
+        |n: f64, env: &Uiua| (Value::from(n).as_shaped_indices(env)).map(|(_, indices)| indices);
+    assert_eq!(shaped_indices(-3.0, &env).unwrap(), [-3]);
+    assert_eq!(
+        shaped_indices(-(2f64.powi(63)), &env).unwrap(),
+        [isize::MIN]
+    );
+    for n in [1e30, -1e30, 2f64.powi(63)] {
+        let err = shaped_indices(n, &env).unwrap_err().to_string();
+        assert!(err.contains("too large to be used as an index"), "{err}");
+    }
+    for n in [f64::NAN, f64::INFINITY, 0.5] {
+        let err = shaped_indices(n, &env).unwrap_err().to_string();
+        assert!(err.contains("is not an integer"), "{err}");
+    }
+    // Huge indices are not silently filled
+    let err = env.run_str("⬚0⊏ 1e30 [1 2 3]").err().unwrap().to_string();
+    assert!(err.contains("too large to be used as an index"), "{err}");
+}
+
+#[test]
+fn scalar_index_select() {
+    let mut env = Uiua::with_native_sys();
+    let cube = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![3, 2, 2],
+        (0..12).map(|i| i as f64).collect::<CowSlice<_>>(),
+    ));
+    let plane = |start: usize| {
+        Value::from(Array::<f64>::new(
+            tinyvec::tiny_vec![2, 2],
+            (start..start + 4)
+                .map(|i| i as f64)
+                .collect::<CowSlice<_>>(),
+        ))
+    };
+    // A scalar index selects a single row without a leading axis
+    let selected = Value::from(1.0).select(&cube, &env).unwrap();
+    assert_eq!(selected.shape(), &[2, 2]);
+    assert_eq!(selected, plane(4));
+    let selected = Value::from(-1.0).select(&cube, &env).unwrap();
+    assert_eq!(selected, plane(8));
+    let selected = Value::from(-3.0).select(&cube, &env).unwrap();
+    assert_eq!(selected, plane(0));
+    // A list with one index keeps the leading axis
+    let selected = Value::from_iter([-1.0]).select(&cube, &env).unwrap();
+    assert_eq!(selected.shape(), &[1, 2, 2]);
+    // Every row of a single-row array is the identity selection
+    let single = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![1, 2, 2],
+        (0..4).map(|i| i as f64).collect::<CowSlice<_>>(),
+    ));
+    assert_eq!(Value::from(0.0).select(&single, &env).unwrap(), plane(0));
+    assert!(Value::from(3.0).select(&cube, &env).is_err());
+    assert!(Value::from(-4.0).select(&cube, &env).is_err());
+    env.with_fill(Value::from(0.0), |env| {
+        let selected = Value::from(5.0).select(&cube, env)?;
+        assert_eq!(selected.shape(), &[2, 2]);
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn identity_select_shares_data() {
+    let env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![3, 2],
+        (0..6).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    let selected = arr.select(&[0, 1, 2], OobMode::Fill, &env).unwrap();
+    assert!(selected.data.is_copy_of(&arr.data));
+    assert_eq!(selected, arr);
+    let selected = arr.select(&[0, 2, 1], OobMode::Fill, &env).unwrap();
+    assert!(!selected.data.is_copy_of(&arr.data));
+    // Metadata is dropped just as it is for other selections
+    let mut map = arr.clone();
+    map.meta_mut().map_len = Some(3);
+    let selected = map.select(&[0, 1, 2], OobMode::Fill, &env).unwrap();
+    assert_eq!(selected.meta().map_len, None);
+}
+
+#[test]
+fn select_index_runs() {
+    let mut env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![4, 3],
+        (0..12).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    // Sorted indices have long runs, including runs of fills
+    let indices: Vec<isize> = (0..5000).map(|i| i / 777 - 1).collect();
+    let expected: Vec<f64> = (indices.iter())
+        .flat_map(|&i| {
+            let i = if i < 0 { i + 4 } else { i };
+            if (0..4).contains(&i) {
+                (0..3).map(|j| (i * 3 + j) as f64).collect()
+            } else {
+                vec![-1.0; 3]
+            }
+        })
+        .collect();
+    env.with_fill(Value::from(-1.0), |env| {
+        let selected = arr.select(&indices, OobMode::Fill, env)?;
+        assert_eq!(&*selected.shape, [5000, 3]);
+        assert_eq!(selected.data.as_slice(), expected);
+        // Runs of every length up to the doubling boundaries
+        for len in 1..=9 {
+            let indices = vec![2; len];
+            let selected = arr.select(&indices, OobMode::Fill, env)?;
+            assert_eq!(selected.data.as_slice(), [6.0, 7.0, 8.0].repeat(len));
+        }
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn select_large_gather() {
+    let mut env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![10, 3],
+        (0..30).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    let indices: Vec<isize> = (0..2000).map(|i| (i * 7) % 23 - 11).collect();
+    let expected = |fill: f64| -> Vec<f64> {
+        (indices.iter())
+            .flat_map(|&i| {
+                let i = if i < 0 { i + 10 } else { i };
+                if (0..10).contains(&i) {
+                    (0..3).map(|j| (i * 3 + j) as f64).collect()
+                } else {
+                    vec![fill; 3]
+                }
+            })
+            .collect()
+    };
+    // The first out-of-bounds index is reported
+    let err = arr.select(&indices, OobMode::Fill, &env).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Index -11 (normalized to -1) is out of bounds of length 10"),
+        "{err}"
+    );
+    env.with_fill(Value::from(-1.0), |env| {
+        let selected = arr.select(&indices, OobMode::Fill, env)?;
+        assert_eq!(&*selected.shape, [2000, 3]);
+        assert_eq!(selected.data.as_slice(), expected(-1.0));
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn select_out_of_bounds_messages() {
+    let env = Uiua::with_native_sys();
+    let arr = Array::<f64>::from_iter([1.0, 2.0, 3.0]);
+    let message = |indices: &[isize]| {
+        arr.select(indices, OobMode::Fill, &env)
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(message(&[5]).contains("Index 5 is out of bounds of length 3"));
+    assert!(message(&[-5]).contains("Index -5 (normalized to -2) is out of bounds of length 3"));
+    let message = |indices: &[isize]| {
+        let selected = Array::<f64>::from_iter(indices.iter().map(|_| 0.0));
+        (selected.unselect(&[indices.len()], indices, arr.clone(), &env))
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(message(&[3]).contains("Index 3 is out of bounds of length 3"));
+    assert!(message(&[-4]).contains("Index -4 (normalized to -1) is out of bounds of length 3"));
+}
+
+#[test]
+fn select_oob_modes() {
+    let mut env = Uiua::with_native_sys();
+    let from = Value::from_iter([10.0, 20.0, 30.0]);
+    let indices = Value::from_iter([-5.0, -1.0, 0.0, 3.0, 7.0]);
+    let select = |mode, env: &Uiua| indices.select_with_mode(&from, mode, env);
+    assert_eq!(
+        select(OobMode::Clamp, &env).unwrap(),
+        Value::from_iter([10.0, 30.0, 10.0, 30.0, 30.0])
+    );
+    assert_eq!(
+        select(OobMode::Wrap, &env).unwrap(),
+        Value::from_iter([20.0, 30.0, 10.0, 10.0, 20.0])
+    );
+    assert!(select(OobMode::Fill, &env).is_err());
+    assert!(select(OobMode::Error, &env).is_err());
+    env.with_fill(Value::from(0.0), |env| {
+        assert_eq!(
+            select(OobMode::Fill, env)?,
+            Value::from_iter([0.0, 30.0, 10.0, 0.0, 0.0])
+        );
+        assert!(select(OobMode::Error, env).is_err());
+        assert_eq!(
+            select(OobMode::Clamp, env)?,
+            Value::from_iter([10.0, 30.0, 10.0, 30.0, 30.0])
+        );
+        Ok(())
+    })
+    .unwrap();
+    // There is no row to clamp or wrap to
+    let empty = Value::from(Array::<f64>::new(tinyvec::tiny_vec![0, 2], CowSlice::new()));
+    for mode in [OobMode::Clamp, OobMode::Wrap] {
+        let err = Value::from(0.0)
+            .select_with_mode(&empty, mode, &env)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("out of bounds of length 0"),
+            "{err}"
+        );
+    }
+    // Modes apply to every row of multidimensional indices
+    let indices = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![2, 2],
+        [-4.0, 4.0, 1.0, 5.0].into_iter().collect::<CowSlice<_>>(),
+    ));
+    let selected = indices
+        .select_with_mode(&from, OobMode::Clamp, &env)
+        .unwrap();
+    assert_eq!(selected.shape(), &[2, 2]);
+    assert_eq!(
+        selected,
+        Value::from(Array::<f64>::new(
+            tinyvec::tiny_vec![2, 2],
+            [10.0, 30.0, 20.0, 30.0]
+                .into_iter()
+                .collect::<CowSlice<_>>(),
+        ))
+    );
+}
+
+#[test]
+fn take_prefix_3d() {
+    let env = Uiua::with_native_sys();
+    let n = 6;
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![n, n, n],
+        (0..n * n * n).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    let (a, b, c) = (4, 3, 2);
+    let taken = arr
+        .clone()
+        .take(&[a as isize, b as isize, c as isize], &env)
+        .unwrap();
+    assert_eq!(&*taken.shape, [a, b, c]);
+    let expected: Vec<f64> = (0..a)
+        .flat_map(|i| (0..b).flat_map(move |j| (0..c).map(move |k| (i * n * n + j * n + k) as f64)))
+        .collect();
+    assert_eq!(taken.data.as_slice(), expected);
+    // Leaving off trailing axes takes whole rows
+    let taken = arr.clone().take(&[2, 3], &env).unwrap();
+    assert_eq!(&*taken.shape, [2, 3, n]);
+    assert_eq!(
+        taken.data[n..n + 3],
+        [n as f64, n as f64 + 1.0, n as f64 + 2.0]
+    );
+    // Negative indices take from the end
+    let taken = arr.take(&[-1, -1, -2], &env).unwrap();
+    assert_eq!(&*taken.shape, [1, 1, 2]);
+    assert_eq!(
+        taken.data.as_slice(),
+        [(n * n * n - 2) as f64, (n * n * n - 1) as f64]
+    );
+}
+
+#[test]
+fn take_drop_scalar_promotion() {
+    let env = Uiua::with_native_sys();
+    assert!(Value::from(1.0).take(5.0.into(), &env).is_err());
+    assert!(Value::from(1.0).drop(5.0.into(), &env).is_err());
+    let env = Uiua::with_native_sys().promote_scalars(true);
+    let taken = Value::from(1.0).take(5.0.into(), &env).unwrap();
+    assert_eq!(taken, Value::from_iter([5.0]));
+    let dropped = Value::from(1.0).drop(5.0.into(), &env).unwrap();
+    assert_eq!(dropped.shape(), &[0]);
+}
+
+#[test]
+fn over_drop_keeps_inner_shape() {
+    let env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![2, 3, 4],
+        (0..24).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    for (index, shape) in [
+        (&[5][..], [0, 3, 4]),
+        (&[-5], [0, 3, 4]),
+        (&[1, 5], [1, 0, 4]),
+        (&[5, 1], [0, 2, 4]),
+        (&[-5, -1], [0, 2, 4]),
+        (&[1, 1, 9], [1, 2, 0]),
+    ] {
+        let dropped = arr.clone().drop(index, &env).unwrap();
+        assert_eq!(*dropped.shape, shape, "dropping {index:?}");
+        assert!(dropped.data.is_empty());
+    }
+}
+
+#[test]
+fn pick_masked() {
+    let mut env = Uiua::with_native_sys();
+    let arr = Array::<f64>::new(
+        tinyvec::tiny_vec![2, 3],
+        (0..6).map(|i| i as f64).collect::<CowSlice<_>>(),
+    );
+    // Three indices into the first two axes; the middle one is bogus but masked
+    let index_shape = [3, 2];
+    let index_data = [1, 2, 5, 7, 0, -1];
+    let mask = [1, 0, 1];
+    assert!(arr
+        .pick_masked(&index_shape, &index_data, &mask, &env)
+        .is_err());
+    env.with_fill(Value::from(-1.0), |env| {
+        let picked = arr.pick_masked(&index_shape, &index_data, &mask, env)?;
+        assert_eq!(&*picked.shape, [3]);
+        assert_eq!(picked.data.as_slice(), [5.0, -1.0, 2.0]);
+        // Masked rows are filled with whole rows
+        let picked = arr.pick_masked(&[2, 1], &[0, 1], &[0, 1], env)?;
+        assert_eq!(&*picked.shape, [2, 3]);
+        assert_eq!(picked.data.as_slice(), [-1.0, -1.0, -1.0, 3.0, 4.0, 5.0]);
+        // The mask must match the number of indices
+        assert!(arr.pick_masked(&[2, 1], &[0, 1], &[1], env).is_err());
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn pick_depth() {
+    let env = Uiua::with_native_sys();
+    // A 2×3×4 array where each item is 100a+10b+c for its index a_b_c
+    let data = (0..24).map(|i| (i / 12 * 100 + i / 4 % 3 * 10 + i % 4) as f64);
+    let arr = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![2, 3, 4],
+        data.collect::<CowSlice<_>>(),
+    ));
+    let nums = |val: Value| match val {
+        Value::Num(arr) => (arr.shape.to_vec(), arr.data.to_vec()),
+        val => panic!("expected numbers, got {val:?}"),
+    };
+    // A scalar index picks a row of every sub-array
+    let picked = Value::from(2.0)
+        .pick_depth(arr.clone(), 0, 1, &env)
+        .unwrap();
+    let (shape, data) = nums(picked);
+    assert_eq!(shape, [2, 4]);
+    assert_eq!(data, [20.0, 21.0, 22.0, 23.0, 120.0, 121.0, 122.0, 123.0]);
+    let picked = Value::from(-1.0)
+        .pick_depth(arr.clone(), 0, 2, &env)
+        .unwrap();
+    let (shape, data) = nums(picked);
+    assert_eq!(shape, [2, 3]);
+    assert_eq!(data, [3.0, 13.0, 23.0, 103.0, 113.0, 123.0]);
+    // Each sub-array can get its own index
+    let index = Value::from(Array::from_iter([0.0, 2.0]));
+    let (shape, data) = nums(index.pick_depth(arr.clone(), 1, 1, &env).unwrap());
+    assert_eq!(shape, [2, 4]);
+    assert_eq!(data, [0.0, 1.0, 2.0, 3.0, 120.0, 121.0, 122.0, 123.0]);
+    // A list index picks along several axes
+    let index = Value::from(Array::from_iter([1.0, 3.0]));
+    let (shape, data) = nums(index.pick_depth(arr.clone(), 0, 1, &env).unwrap());
+    assert_eq!(shape, [2]);
+    assert_eq!(data, [13.0, 113.0]);
+    // Empty outer axes keep their shape
+    let empty = Value::from(Array::<f64>::new(
+        tinyvec::tiny_vec![0, 3, 4],
+        CowSlice::new(),
+    ));
+    let (shape, _) = nums(Value::from(1.0).pick_depth(empty, 0, 1, &env).unwrap());
+    assert_eq!(shape, [0, 4]);
+    // Indices must be in bounds without a fill
+    assert!(Value::from(3.0).pick_depth(arr, 0, 1, &env).is_err());
+}
+
+#[cfg(test)]
+mod take_drop_props {
+    use quickcheck::{quickcheck, Arbitrary, Gen, TestResult};
+
+    use super::*;
+
+    /// A small array shape along with a `take`/`drop` index
+    ///
+    /// Index components may be up to 2 past the corresponding axis length
+    /// so that fill behavior gets exercised as well.
+    #[derive(Debug, Clone)]
+    struct Case {
+        shape: Vec<usize>,
+        index: Vec<isize>,
+    }
+
+    impl Case {
+        fn array(&self) -> Array<f64> {
+            let len: usize = self.shape.iter().product();
+            Array::new(
+                self.shape.iter().copied().collect::<Shape>(),
+                (0..len).map(|i| i as f64).collect::<CowSlice<_>>(),
+            )
+        }
+        fn in_bounds(&self) -> bool {
+            (self.index.iter().zip(&self.shape)).all(|(&i, &s)| i.unsigned_abs() <= s)
+        }
+        fn is_valid(&self) -> bool {
+            !self.shape.is_empty() && self.index.len() <= self.shape.len()
+        }
+    }
+
+    impl Arbitrary for Case {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let rank = 1 + usize::arbitrary(g) % 3;
+            let shape: Vec<usize> = (0..rank).map(|_| usize::arbitrary(g) % 5).collect();
+            let index_len = usize::arbitrary(g) % (rank + 1);
+            let index = (shape.iter().take(index_len))
+                .map(|&s| {
+                    let bound = s as isize + 2;
+                    isize::arbitrary(g).rem_euclid(2 * bound + 1) - bound
+                })
+                .collect();
+            Case { shape, index }
+        }
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            Box::new(
+                (self.shape.clone(), self.index.clone())
+                    .shrink()
+                    .map(|(shape, index)| Case { shape, index })
+                    .filter(Case::is_valid),
+            )
+        }
+    }
+
+    fn expected_take_shape(case: &Case) -> Shape {
+        let mut shape: Shape = case.shape.iter().copied().collect();
+        for (s, &i) in shape.iter_mut().zip(&case.index) {
+            *s = i.unsigned_abs();
+        }
+        shape
+    }
+
+    fn expected_drop_shape(case: &Case) -> Shape {
+        let mut shape: Shape = case.shape.iter().copied().collect();
+        for (s, &i) in shape.iter_mut().zip(&case.index) {
+            *s = s.saturating_sub(i.unsigned_abs());
+        }
+        shape
+    }
+
+    fn has_valid_shape<T>(arr: &Array<T>) -> bool {
+        arr.shape.iter().product::<usize>() == arr.data.len()
+    }
+
+    #[test]
+    fn take_untake_roundtrip() {
+        fn prop(case: Case) -> TestResult {
+            if !case.is_valid() || !case.in_bounds() {
+                return TestResult::discard();
+            }
+            let env = Uiua::with_native_sys();
+            let arr = case.array();
+            let taken = arr.clone().take(&case.index, &env).unwrap();
+            if !has_valid_shape(&taken) || taken.shape != expected_take_shape(&case) {
+                return TestResult::error(format!("taken shape is {:?}", taken.shape));
+            }
+            let untaken = taken.untake(&case.index, arr.clone(), &env).unwrap();
+            TestResult::from_bool(untaken == arr)
+        }
+        quickcheck(prop as fn(Case) -> TestResult);
+    }
+
+    #[test]
+    fn drop_undrop_roundtrip() {
+        fn prop(case: Case) -> TestResult {
+            if !case.is_valid() || !case.in_bounds() {
+                return TestResult::discard();
+            }
+            let env = Uiua::with_native_sys();
+            let arr = case.array();
+            let dropped = arr.clone().drop(&case.index, &env).unwrap();
+            if !has_valid_shape(&dropped) || dropped.shape != expected_drop_shape(&case) {
+                return TestResult::error(format!("dropped shape is {:?}", dropped.shape));
+            }
+            let undropped = dropped.undrop(&case.index, arr.clone(), &env).unwrap();
+            TestResult::from_bool(undropped == arr)
+        }
+        quickcheck(prop as fn(Case) -> TestResult);
+    }
+
+    #[test]
+    fn over_drop_keeps_inner_shape() {
+        fn prop(case: Case) -> TestResult {
+            if !case.is_valid() || case.in_bounds() {
+                return TestResult::discard();
+            }
+            let mut env = Uiua::with_native_sys();
+            let arr = case.array();
+            let expected = expected_drop_shape(&case);
+            let dropped = arr.clone().drop(&case.index, &env).unwrap();
+            if !has_valid_shape(&dropped) || dropped.shape != expected {
+                return TestResult::error(format!("dropped shape is {:?}", dropped.shape));
+            }
+            // A fill does not change the result of dropping
+            let filled = env.with_fill(Value::from(-1.0), |env| {
+                let filled = arr.drop(&case.index, env)?;
+                assert_eq!(filled, dropped);
+                Ok(())
+            });
+            TestResult::from_bool(filled.is_ok())
+        }
+        quickcheck(prop as fn(Case) -> TestResult);
+    }
+
+    #[test]
+    fn filled_take_has_index_shape() {
+        fn prop(case: Case) -> TestResult {
+            if !case.is_valid() {
+                return TestResult::discard();
+            }
+            let mut env = Uiua::with_native_sys();
+            let arr = case.array();
+            let mut taken = None;
+            env.with_fill(0.0.into(), |env| {
+                taken = Some(arr.take(&case.index, env)?);
+                Ok(())
+            })
+            .unwrap();
+            let taken = taken.unwrap();
+            TestResult::from_bool(
+                has_valid_shape(&taken) && taken.shape == expected_take_shape(&case),
+            )
+        }
+        quickcheck(prop as fn(Case) -> TestResult);
+    }
+}
@@ -23,6 +23,8 @@ pub mod reduce;
 pub mod table;
 pub mod zip;
 
+pub use dyadic::{CountsMode, OobMode, RotateMode};
+
 type MultiOutput<T> = TinyVec<[T; 1]>;
 fn multi_output<T: Clone + Default>(n: usize, val: T) -> MultiOutput<T> {
     let mut vec = TinyVec::with_capacity(n);
@@ -161,7 +163,7 @@ where
         {
             a.shape.drain(..fixes);
             for &dim in b.shape[..fixes].iter().rev() {
-                a.reshape_scalar(dim);
+                a.repeat_as_rows(dim);
             }
         }
     }
@@ -174,7 +176,7 @@ where
         {
             b.shape.drain(..fixes);
             for &dim in a.shape[..fixes].iter().rev() {
-                b.reshape_scalar(dim);
+                b.repeat_as_rows(dim);
             }
         }
     }
@@ -388,6 +390,45 @@ pub fn switch(count: usize, sig: Signature, env: &mut Uiua) -> UiuaResult {
     }
 }
 
+/// Call `f` on each `chunk_len`-long chunk of `items`, along with the chunk's index
+///
+/// With the `rayon` feature, the chunks are processed in parallel if `parallel`
+/// is set, and each thread gets its own scratch value from `init`.
+/// Otherwise, one scratch value is used for every chunk.
+pub(crate) fn for_each_chunk<T: Send, S>(
+    items: &mut [T],
+    chunk_len: usize,
+    parallel: bool,
+    init: impl Fn() -> S + Sync + Send,
+    f: impl Fn(&mut S, usize, &mut [T]) + Sync + Send,
+) {
+    #[cfg(feature = "rayon")]
+    if parallel {
+        use rayon::prelude::*;
+        (items.par_chunks_mut(chunk_len).enumerate())
+            .for_each_init(init, |scratch, (i, chunk)| f(scratch, i, chunk));
+        return;
+    }
+    #[cfg(not(feature = "rayon"))]
+    let _ = parallel;
+    let mut scratch = init();
+    for (i, chunk) in items.chunks_mut(chunk_len).enumerate() {
+        f(&mut scratch, i, chunk);
+    }
+}
+
+/// Stably sort `items` with a comparator function,
+/// in parallel with the `rayon` feature
+pub(crate) fn sort_by<T: Send>(items: &mut [T], compare: impl Fn(&T, &T) -> Ordering + Sync) {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        items.par_sort_by(compare);
+    }
+    #[cfg(not(feature = "rayon"))]
+    items.sort_by(compare);
+}
+
 /// If a function fails on a byte array because no fill byte is defined,
 /// convert the byte array to a number array and try again.
 fn op_bytes_retry_fill<T>(
@@ -431,6 +472,10 @@ fn op2_bytes_retry_fill<T, C: FillContext>(
     }
 }
 
+/// A row that can be compared and hashed by its elements
+///
+/// NaN is equal to NaN here, so that rows containing NaN can be found at all.
+/// Operations that let NaN never match check for it separately.
 struct ArrayCmpSlice<'a, T>(&'a [T]);
 
 impl<'a, T: ArrayValue> PartialEq for ArrayCmpSlice<'a, T> {
@@ -2,7 +2,7 @@
 
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, BTreeSet, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     f64::consts::{PI, TAU},
     iter::repeat,
     mem::size_of,
@@ -10,7 +10,6 @@ use std::{
 };
 
 use ecow::{eco_vec, EcoVec};
-use rayon::prelude::*;
 use tinyvec::tiny_vec;
 
 use crate::{
@@ -21,7 +20,7 @@ use crate::{
     Boxed, Uiua, UiuaResult,
 };
 
-use super::{op_bytes_retry_fill, ArrayCmpSlice, FillContext};
+use super::{for_each_chunk, op_bytes_retry_fill, sort_by, ArrayCmpSlice, FillContext};
 
 impl Value {
     /// Make the value 1-dimensional
@@ -486,16 +485,17 @@ impl<T: ArrayValue> Array<T> {
             let mut temp = data.to_vec();
             let row_count = self.shape[depth];
             let row_len = data.len() / row_count;
-            let op = |(j, chunk): (usize, &mut [T])| {
-                for (i, item) in chunk.iter_mut().enumerate() {
-                    *item = data[i * row_len + j].clone();
-                }
-            };
-            if row_count > 500 {
-                temp.par_chunks_mut(row_count).enumerate().for_each(op);
-            } else {
-                temp.chunks_mut(row_count).enumerate().for_each(op);
-            }
+            for_each_chunk(
+                &mut temp,
+                row_count,
+                row_count > 500,
+                || (),
+                |_, j, chunk| {
+                    for (i, item) in chunk.iter_mut().enumerate() {
+                        *item = data[i * row_len + j].clone();
+                    }
+                },
+            );
             data.clone_from_slice(&temp);
         }
         self.shape[depth..].rotate_left(1);
@@ -519,16 +519,17 @@ impl<T: ArrayValue> Array<T> {
             let mut temp = data.to_vec();
             let col_count: usize = self.shape[depth..].iter().rev().skip(1).product();
             let col_len = *self.shape.last().unwrap();
-            let op = |(j, chunk): (usize, &mut [T])| {
-                for (i, item) in chunk.iter_mut().enumerate() {
-                    *item = data[i * col_len + j].clone();
-                }
-            };
-            if col_count > 500 {
-                temp.par_chunks_mut(col_count).enumerate().for_each(op);
-            } else {
-                temp.chunks_mut(col_count).enumerate().for_each(op);
-            }
+            for_each_chunk(
+                &mut temp,
+                col_count,
+                col_count > 500,
+                || (),
+                |_, j, chunk| {
+                    for (i, item) in chunk.iter_mut().enumerate() {
+                        *item = data[i * col_len + j].clone();
+                    }
+                },
+            );
             data.clone_from_slice(&temp);
         }
         self.shape[depth..].rotate_right(1);
@@ -580,6 +581,14 @@ impl Value {
             Array::deduplicate,
         )
     }
+    /// Get the unique rows of the value, in the order they first appear
+    ///
+    /// This is a non-mutating [`Value::deduplicate`].
+    pub fn unique(&self) -> Self {
+        let mut unique = self.clone();
+        unique.deduplicate();
+        unique
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
@@ -592,7 +601,7 @@ impl<T: ArrayValue> Array<T> {
             return Ok(Vec::new());
         }
         let mut indices = (0..self.row_count()).collect::<Vec<_>>();
-        indices.par_sort_by(|&a, &b| {
+        sort_by(&mut indices, |&a, &b| {
             self.row_slice(a)
                 .iter()
                 .zip(self.row_slice(b))
@@ -611,7 +620,7 @@ impl<T: ArrayValue> Array<T> {
             return Ok(Vec::new());
         }
         let mut indices = (0..self.row_count()).collect::<Vec<_>>();
-        indices.par_sort_by(|&a, &b| {
+        sort_by(&mut indices, |&a, &b| {
             self.row_slice(a)
                 .iter()
                 .zip(self.row_slice(b))
@@ -637,23 +646,58 @@ impl<T: ArrayValue> Array<T> {
     }
     /// `deduplicate` the rows of the array
     pub fn deduplicate(&mut self) {
+        let unique = self.unique();
+        self.shape = unique.shape;
+        self.data = unique.data;
+    }
+    /// Get the unique rows of the array, in the order they first appear
+    ///
+    /// Scalars are returned unchanged.
+    pub fn unique(&self) -> Self {
         if self.rank() == 0 {
-            return;
+            return self.clone();
         }
-        let mut deduped = CowSlice::new();
-        let mut seen = BTreeSet::new();
-        let mut new_len = 0;
-        for row in self.rows() {
-            if seen.insert(row.clone()) {
-                deduped.extend_from_slice(&row.data);
-                new_len += 1;
+        let mut seen = HashSet::with_capacity(self.row_count());
+        let mut unique = EcoVec::new();
+        let mut unique_count = 0;
+        for row in self.row_slices() {
+            if seen.insert(ArrayCmpSlice(row)) {
+                unique.extend_from_slice(row);
+                unique_count += 1;
             }
         }
-        self.data = deduped;
-        self.shape[0] = new_len;
+        let mut shape = self.shape.clone();
+        shape[0] = unique_count;
+        Array::new(shape, unique)
     }
 }
 
+#[test]
+fn unique_rows() {
+    let table = Array::<f64>::new(
+        tiny_vec![5, 2],
+        [1.0, 2.0, 3.0, 4.0, 1.0, 2.0, f64::NAN, 0.0, f64::NAN, -0.0]
+            .into_iter()
+            .collect::<CowSlice<_>>(),
+    );
+    let unique = table.unique();
+    assert_eq!(&*unique.shape, [3, 2]);
+    assert_eq!(unique.data[..4], [1.0, 2.0, 3.0, 4.0]);
+    assert!(unique.data[4].is_nan());
+    // Scalars and empty arrays are unchanged
+    assert_eq!(Array::from(5.0).unique(), Array::from(5.0));
+    let empty = Array::<f64>::new(tiny_vec![0, 3], CowSlice::new());
+    assert_eq!(empty.unique(), empty);
+    // Deduplicating in place keeps the metadata
+    let mut deduped = table.clone();
+    deduped.meta_mut().map_len = Some(5);
+    deduped.deduplicate();
+    assert_eq!(deduped, unique);
+    assert_eq!(deduped.meta().map_len, Some(5));
+    let value = Value::from(table);
+    assert_eq!(value.unique(), Value::from(unique));
+}
+
 impl Value {
     /// Encode the `bits` of the value
     pub fn bits(&self, env: &Uiua) -> UiuaResult<Array<u8>> {
@@ -199,12 +199,9 @@ impl<T: ArrayValue> Array<T> {
         row_shape: Shape,
     ) -> impl ExactSizeIterator<Item = Self> + DoubleEndedIterator + '_ {
         let row_len: usize = row_shape.iter().product();
-        let row_count = self.element_count() / row_len;
-        (0..row_count).map(move |i| {
-            let start = i * row_len;
-            let end = start + row_len;
-            Self::new(row_shape.clone(), self.data.slice(start..end))
-        })
+        self.data
+            .slices(row_len)
+            .map(move |data| Self::new(row_shape.clone(), data))
     }
     /// Get an iterator over the row arrays of the array that have the given shape
     pub fn into_row_shaped_slices(
@@ -452,6 +449,10 @@ pub trait ArrayValue: Clone + Debug + Display + GridFmt + ArrayCmp + Send + Sync
     fn array_hash<H: Hasher>(&self, hasher: &mut H);
     /// Get the proxy value
     fn proxy() -> Self;
+    /// Whether the value is or contains a NaN
+    fn array_is_nan(&self) -> bool {
+        false
+    }
     /// Delimiters for formatting
     fn format_delims() -> (&'static str, &'static str) {
         ("[", "]")
@@ -478,6 +479,9 @@ impl ArrayValue for f64 {
         };
         v.to_bits().hash(hasher)
     }
+    fn array_is_nan(&self) -> bool {
+        self.is_nan()
+    }
     fn proxy() -> Self {
         0.0
     }
@@ -542,6 +546,9 @@ impl ArrayValue for Complex {
             n.array_hash(hasher);
         }
     }
+    fn array_is_nan(&self) -> bool {
+        self.re.is_nan() || self.im.is_nan()
+    }
     fn proxy() -> Self {
         Complex::new(0.0, 0.0)
     }
@@ -3,7 +3,8 @@ use std::{
     cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
-    ops::{Bound, Deref, RangeBounds},
+    mem::take,
+    ops::{Bound, Deref, Range, RangeBounds},
     ptr,
 };
 
@@ -11,12 +12,9 @@ macro_rules! cowslice {
     ($($item:expr),* $(,)?) => {
         $crate::cowslice::CowSlice::from([$($item),*])
     };
-    ($item:expr; $len:expr) => {{
-        let len = $len;
-        let mut cs = $crate::cowslice::CowSlice::with_capacity(len);
-        cs.extend(std::iter::repeat($item).take(len));
-        cs
-    }}
+    ($item:expr; $len:expr) => {
+        $crate::cowslice::CowSlice::from_elem($item, $len)
+    };
 }
 
 pub(crate) use cowslice;
@@ -56,7 +54,64 @@ impl<T> CowSlice<T> {
     }
 }
 
+impl<T> CowSlice<T> {
+    /// Binary search this sorted slice with a comparator function
+    ///
+    /// Only the elements in this slice's view are searched,
+    /// and the returned index is relative to the start of the view
+    pub fn binary_search_by(&self, f: impl FnMut(&T) -> Ordering) -> Result<usize, usize> {
+        self.as_slice().binary_search_by(f)
+    }
+    /// Find the index of the first element that satisfies a predicate
+    ///
+    /// The index is relative to the start of the view
+    ///
+    /// ```ignore
+    /// let slice = cowslice![1, 2, 3, 2];
+    /// assert_eq!(slice.position(|&x| x == 2), Some(1));
+    /// assert_eq!(slice.slice(2..).position(|&x| x == 2), Some(1));
+    /// assert_eq!(slice.position(|&x| x == 4), None);
+    /// ```
+    pub fn position(&self, f: impl FnMut(&T) -> bool) -> Option<usize> {
+        self.as_slice().iter().position(f)
+    }
+    /// Find the index of the last element that satisfies a predicate
+    ///
+    /// The index is relative to the start of the view
+    ///
+    /// ```ignore
+    /// let slice = cowslice![1, 2, 3, 2];
+    /// assert_eq!(slice.rposition(|&x| x == 2), Some(3));
+    /// assert_eq!(slice.slice(..3).rposition(|&x| x == 2), Some(1));
+    /// assert_eq!(slice.rposition(|&x| x == 4), None);
+    /// ```
+    pub fn rposition(&self, f: impl FnMut(&T) -> bool) -> Option<usize> {
+        self.as_slice().iter().rposition(f)
+    }
+}
+
 impl<T: Clone> CowSlice<T> {
+    /// Create a slice of `len` copies of `value`
+    ///
+    /// This is the function form of `cowslice![value; len]`.
+    pub fn from_elem(value: T, len: usize) -> Self {
+        Self {
+            data: EcoVec::from_elem(value, len),
+            start: 0,
+            end: len,
+        }
+    }
+    /// Set every element to `value`
+    ///
+    /// A shared buffer is copied at most once.
+    pub fn fill(&mut self, value: T) {
+        self.as_mut_slice().fill(value)
+    }
+    /// Get mutable access to the elements
+    ///
+    /// If the buffer is shared, the elements are copied first,
+    /// even if nothing ends up being written.
+    /// Use [`CowSlice::get_mut_if_unique`] to avoid the copy.
     pub fn as_mut_slice(&mut self) -> &mut [T] {
         if !self.data.is_unique() {
             let mut new_data = EcoVec::with_capacity(self.len());
@@ -67,9 +122,51 @@ impl<T: Clone> CowSlice<T> {
         }
         &mut self.data.make_mut()[self.start..self.end]
     }
+    /// Iterate over mutable references to the elements
+    ///
+    /// Like [`CowSlice::as_mut_slice`], this copies a shared buffer up front.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+    /// Get mutable access to the elements only if that does not require a copy
+    ///
+    /// This returns `None` if the buffer is shared or this is a view into
+    /// part of it. Use [`CowSlice::as_mut_slice`] to copy in that case.
+    pub fn get_mut_if_unique(&mut self) -> Option<&mut [T]> {
+        if self.data.is_unique() && self.start == 0 && self.end == self.data.len() {
+            Some(self.data.make_mut())
+        } else {
+            None
+        }
+    }
     pub fn extend_from_slice(&mut self, other: &[T]) {
         self.modify(|vec| vec.extend_from_slice(other))
     }
+    /// Append the elements in `src` to the end of the slice `times` times
+    ///
+    /// The elements are copied from the slice itself, so unlike cloning
+    /// the slice and extending from the clone, the buffer is not shared
+    /// while it grows.
+    ///
+    /// # Panics
+    /// Panics if `src` is out of bounds
+    #[track_caller]
+    pub fn extend_from_within(&mut self, src: Range<usize>, times: usize) {
+        let len = self.len();
+        assert!(
+            src.start <= src.end && src.end <= len,
+            "source range {src:?} should be within len {len}"
+        );
+        self.modify(|vec| {
+            vec.reserve(src.len() * times);
+            for _ in 0..times {
+                for i in src.clone() {
+                    let item = vec[i].clone();
+                    vec.push(item);
+                }
+            }
+        })
+    }
     pub fn try_extend<E>(&mut self, iter: impl IntoIterator<Item = Result<T, E>>) -> Result<(), E> {
         self.modify(|vec| {
             for item in iter {
@@ -101,6 +198,21 @@ impl<T: Clone> CowSlice<T> {
             end,
         }
     }
+    /// Split into equal-size slices that share this slice's buffer
+    pub fn slices(
+        &self,
+        size: usize,
+    ) -> impl ExactSizeIterator<Item = Self> + DoubleEndedIterator + '_ {
+        assert!(self.len() % size == 0);
+        (0..self.len() / size).map(move |i| {
+            let start = self.start + (i * size);
+            Self {
+                data: self.data.clone(),
+                start,
+                end: start + size,
+            }
+        })
+    }
     pub fn into_slices(
         self,
         size: usize,
@@ -136,6 +248,21 @@ impl<T: Clone> CowSlice<T> {
             self.modify(|vec| vec.reserve(min - vec.len()))
         }
     }
+    /// Ensure that there is space for exactly `additional` more elements
+    ///
+    /// Unlike [`CowSlice::reserve_min`], which takes a total capacity and may
+    /// over-allocate to amortize growth, this takes a number of elements to add
+    /// and does not leave any slack beyond it.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        let target = self.len() + additional;
+        let owned = self.data.is_unique() && self.start == 0 && self.end == self.data.len();
+        if owned && self.data.capacity() >= target {
+            return;
+        }
+        let mut vec = EcoVec::with_capacity(target);
+        vec.extend(self.iter().cloned());
+        *self = vec.into();
+    }
     pub fn split_off(&mut self, at: usize) -> Self {
         assert!(at <= self.len());
         let mut other = Self::with_capacity(self.len() - at);
@@ -143,6 +270,122 @@ impl<T: Clone> CowSlice<T> {
         self.truncate(at);
         other
     }
+    /// Split into two slices at an index without copying
+    ///
+    /// Unlike [`CowSlice::split_off`], both halves share this slice's buffer.
+    /// Mutating either one copies it first.
+    #[track_caller]
+    pub fn split_at(&self, mid: usize) -> (Self, Self) {
+        assert!(mid <= self.len());
+        let mid = self.start + mid;
+        let left = Self {
+            data: self.data.clone(),
+            start: self.start,
+            end: mid,
+        };
+        let right = Self {
+            data: self.data.clone(),
+            start: mid,
+            end: self.end,
+        };
+        (left, right)
+    }
+    /// Retain only the elements for which the predicate returns `true`
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        if self.data.is_unique() && self.start == 0 && self.end == self.data.len() {
+            self.data.retain(|item| f(item));
+            self.end = self.data.len();
+        } else {
+            *self = self.iter().filter(|item| f(item)).cloned().collect();
+        }
+    }
+    /// Move all of `other`'s elements onto the end of this slice, leaving `other` empty
+    pub fn append(&mut self, other: &mut Self) {
+        let mut other = take(other);
+        if self.is_empty() {
+            *self = other;
+        } else if other.data.is_unique() && other.start == 0 && other.end == other.data.len() {
+            self.modify(|vec| vec.extend(other.data))
+        } else {
+            self.extend_from_slice(&other)
+        }
+    }
+    /// Insert an element at an index, shifting all elements after it to the right
+    ///
+    /// # Panics
+    /// Panics if `index > len`
+    #[track_caller]
+    pub fn insert(&mut self, index: usize, value: T) {
+        let len = self.len();
+        assert!(
+            index <= len,
+            "insertion index (is {index}) should be <= len (is {len})"
+        );
+        self.modify(|vec| vec.insert(index, value))
+    }
+    /// Remove and return the element at an index, shifting all elements after it to the left
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds
+    #[track_caller]
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(
+            index < len,
+            "removal index (is {index}) should be < len (is {len})"
+        );
+        self.modify(|vec| vec.remove(index))
+    }
+    /// Remove the element at `index`, replacing it with the last element
+    ///
+    /// This does not preserve order, but is *O*(1) if the buffer is unique.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds
+    #[track_caller]
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(
+            index < len,
+            "swap_remove index (is {index}) should be < len (is {len})"
+        );
+        self.modify(|vec| {
+            vec.make_mut().swap(index, len - 1);
+            vec.pop().unwrap()
+        })
+    }
+    /// Remove consecutive elements for which `same` returns `true`
+    ///
+    /// As with [`Vec::dedup_by`], `same` is passed each element along with
+    /// the last kept element before it, and the first of each run is kept.
+    pub fn dedup_by(&mut self, mut same: impl FnMut(&T, &T) -> bool) {
+        self.modify(|vec| {
+            let slice = vec.make_mut();
+            let mut len = usize::from(!slice.is_empty());
+            for i in 1..slice.len() {
+                if !same(&slice[i], &slice[len - 1]) {
+                    slice.swap(i, len);
+                    len += 1;
+                }
+            }
+            vec.truncate(len);
+        })
+    }
+    /// Stably sort the slice with a comparator function
+    pub fn sort_by(&mut self, f: impl FnMut(&T, &T) -> Ordering) {
+        self.as_mut_slice().sort_by(f)
+    }
+    /// Sort the slice with a comparator function, without preserving the order of equal elements
+    pub fn sort_unstable_by(&mut self, f: impl FnMut(&T, &T) -> Ordering) {
+        self.as_mut_slice().sort_unstable_by(f)
+    }
+}
+
+impl<T: Clone + Ord> CowSlice<T> {
+    /// Stably sort the slice
+    pub fn sort(&mut self) {
+        self.as_mut_slice().sort()
+    }
 }
 
 #[test]
@@ -157,6 +400,282 @@ fn cow_slice_modify() {
     assert_eq!(sub, [2, 3, 5]);
 }
 
+#[test]
+fn cow_slice_retain() {
+    let mut slice = CowSlice::from([1, 2, 3, 4, 5, 6]);
+    slice.retain(|&n| n % 2 == 0);
+    assert_eq!(slice, [2, 4, 6]);
+
+    let full = CowSlice::from([1, 2, 3, 4, 5, 6]);
+    let mut sub = full.slice(1..5);
+    sub.retain(|&n| n != 3);
+    assert_eq!(full, [1, 2, 3, 4, 5, 6]);
+    assert_eq!(sub, [2, 4, 5]);
+}
+
+#[test]
+fn cow_slice_append() {
+    let mut a = CowSlice::from([1, 2, 3]);
+    let mut b = CowSlice::from([4, 5]);
+    a.append(&mut b);
+    assert_eq!(a, [1, 2, 3, 4, 5]);
+    assert!(b.is_empty());
+
+    let full = CowSlice::from([6, 7, 8, 9]);
+    let mut head = a.slice(..2);
+    let mut sub = full.slice(1..3);
+    head.append(&mut sub);
+    assert_eq!(head, [1, 2, 7, 8]);
+    assert_eq!(a, [1, 2, 3, 4, 5]);
+    assert_eq!(full, [6, 7, 8, 9]);
+    assert!(sub.is_empty());
+}
+
+#[test]
+fn cow_slice_binary_search_by() {
+    let slice = CowSlice::from([1, 3, 5, 7, 9, 11]);
+    let sub = slice.slice(2..5);
+    assert_eq!(sub.binary_search_by(|n| n.cmp(&7)), Ok(1));
+    assert_eq!(sub.binary_search_by(|n| n.cmp(&1)), Err(0));
+    assert_eq!(sub.binary_search_by(|n| n.cmp(&10)), Err(3));
+    assert_eq!(sub.binary_search_by(|n| n.cmp(&11)), Err(3));
+}
+
+#[test]
+fn cow_slice_insert_remove() {
+    let full = CowSlice::from([1, 2, 3, 4, 5]);
+    let mut sub = full.slice(1..4);
+    sub.insert(1, 7);
+    assert_eq!(sub, [2, 7, 3, 4]);
+    sub.insert(4, 8);
+    assert_eq!(sub, [2, 7, 3, 4, 8]);
+    assert_eq!(sub.remove(0), 2);
+    assert_eq!(sub, [7, 3, 4, 8]);
+    assert_eq!(full, [1, 2, 3, 4, 5]);
+
+    let mut sub = full.slice(2..);
+    assert_eq!(sub.remove(2), 5);
+    assert_eq!(sub, [3, 4]);
+    assert_eq!(full, [1, 2, 3, 4, 5]);
+}
+
+#[test]
+#[should_panic]
+fn cow_slice_remove_out_of_bounds() {
+    let full = CowSlice::from([1, 2, 3, 4, 5]);
+    let mut sub = full.slice(1..3);
+    sub.remove(2);
+}
+
+#[test]
+fn cow_slice_extend_from_within() {
+    let full = CowSlice::from([1, 2, 3, 4, 5]);
+    let mut sub = full.slice(1..4);
+    sub.extend_from_within(1..3, 2);
+    assert_eq!(sub, [2, 3, 4, 3, 4, 3, 4]);
+    assert_eq!(full, [1, 2, 3, 4, 5]);
+
+    let mut slice = CowSlice::from([1, 2]);
+    slice.extend_from_within(0..2, 0);
+    assert_eq!(slice, [1, 2]);
+    slice.extend_from_within(0..0, 3);
+    assert_eq!(slice, [1, 2]);
+    slice.extend_from_within(0..2, 1);
+    assert_eq!(slice, [1, 2, 1, 2]);
+}
+
+#[test]
+#[should_panic]
+fn cow_slice_extend_from_within_out_of_bounds() {
+    CowSlice::from([1, 2, 3])
+        .slice(1..)
+        .extend_from_within(1..3, 1);
+}
+
+#[test]
+fn cow_slice_swap_remove() {
+    let full = CowSlice::from([1, 2, 3, 4, 5]);
+    let mut sub = full.slice(1..4);
+    assert_eq!(sub.swap_remove(0), 2);
+    assert_eq!(sub, [4, 3]);
+    assert_eq!(sub.swap_remove(1), 3);
+    assert_eq!(sub, [4]);
+    assert_eq!(full, [1, 2, 3, 4, 5]);
+}
+
+#[test]
+#[should_panic]
+fn cow_slice_swap_remove_out_of_bounds() {
+    let full = CowSlice::from([1, 2, 3, 4, 5]);
+    let mut sub = full.slice(1..3);
+    sub.swap_remove(2);
+}
+
+#[test]
+fn cow_slice_from_iter_capacity() {
+    // A sized iterator is collected into a single allocation of the right size
+    let slice: CowSlice<usize> = (0..10_000).collect();
+    assert_eq!(slice.len(), 10_000);
+    assert_eq!(slice.data.capacity(), 10_000);
+    // Iterators without a useful size hint still collect everything
+    let slice: CowSlice<usize> = (0..10_000).filter(|n| n % 3 == 0).collect();
+    assert_eq!(slice.len(), 3334);
+    assert!(slice.iter().enumerate().all(|(i, &n)| n == i * 3));
+}
+
+#[test]
+fn cow_slice_reserve_exact() {
+    let mut slice = CowSlice::from([1, 2, 3]);
+    slice.reserve_exact(37);
+    assert_eq!(slice.data.capacity(), 40);
+    assert_eq!(slice, [1, 2, 3]);
+    // Enough space is already there
+    slice.reserve_exact(5);
+    assert_eq!(slice.data.capacity(), 40);
+
+    // A shared slice gets its own buffer
+    let full = CowSlice::from([1, 2, 3, 4, 5]);
+    let mut sub = full.slice(1..3);
+    sub.reserve_exact(3);
+    assert_eq!(sub.data.capacity(), 5);
+    sub.extend_from_slice(&[6, 7, 8]);
+    assert_eq!(sub, [2, 3, 6, 7, 8]);
+    assert_eq!(full, [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn cow_slice_slices() {
+    let full = CowSlice::from([0, 1, 2, 3, 4, 5, 6, 7]);
+    let sub = full.slice(2..8);
+    let slices: Vec<_> = sub.slices(2).collect();
+    assert_eq!(slices, [[2, 3], [4, 5], [6, 7]]);
+    for (i, slice) in slices.iter().enumerate() {
+        assert!(ptr::eq(slice.as_ptr(), full[2 + 2 * i..].as_ptr()));
+    }
+    assert_eq!(sub.slices(3).next_back().unwrap(), [5, 6, 7]);
+}
+
+#[test]
+fn cow_slice_get_mut_if_unique() {
+    let mut full = CowSlice::from([1, 2, 3]);
+    full.get_mut_if_unique().unwrap()[0] = 10;
+    assert_eq!(full, [10, 2, 3]);
+    let mut shared = full.clone();
+    assert!(shared.get_mut_if_unique().is_none());
+    let mut sub = full.slice(1..);
+    assert!(sub.get_mut_if_unique().is_none());
+    drop(full);
+    // Still a partial view, even though nothing else refers to the buffer
+    assert!(sub.get_mut_if_unique().is_none());
+    assert!(shared.get_mut_if_unique().is_none());
+    drop(sub);
+    shared.get_mut_if_unique().unwrap()[2] = 30;
+    assert_eq!(shared, [10, 2, 30]);
+}
+
+#[test]
+fn cow_slice_iter_mut() {
+    let mut full = CowSlice::from([1, 2, 3]);
+    let shared = full.clone();
+    // Iterating copies the shared buffer even without writing
+    assert_eq!(full.iter_mut().count(), 3);
+    assert!(!full.is_copy_of(&shared));
+    assert!(full.get_mut_if_unique().is_some());
+    for n in &mut full {
+        *n *= 10;
+    }
+    assert_eq!(full, [10, 20, 30]);
+    assert_eq!(shared, [1, 2, 3]);
+}
+
+#[test]
+fn cow_slice_split_at() {
+    let full = CowSlice::from([0, 1, 2, 3, 4, 5]);
+    let sub = full.slice(1..);
+    let (mut left, mut right) = sub.split_at(2);
+    assert_eq!(left, [1, 2]);
+    assert_eq!(right, [3, 4, 5]);
+    assert!(ptr::eq(left.as_ptr(), full[1..].as_ptr()));
+    assert!(ptr::eq(right.as_ptr(), full[3..].as_ptr()));
+    // Each half is copied on write without affecting the others
+    left.as_mut_slice()[0] = 10;
+    right.as_mut_slice()[0] = 30;
+    assert_eq!(left, [10, 2]);
+    assert_eq!(right, [30, 4, 5]);
+    assert_eq!(sub, [1, 2, 3, 4, 5]);
+    assert_eq!(full, [0, 1, 2, 3, 4, 5]);
+    let (empty, all) = full.split_at(0);
+    assert!(empty.is_empty());
+    assert_eq!(all, full);
+}
+
+#[test]
+#[should_panic]
+fn cow_slice_split_at_out_of_bounds() {
+    CowSlice::from([1, 2, 3]).split_at(4);
+}
+
+#[test]
+fn cow_slice_from_elem_fill() {
+    let slice = CowSlice::from_elem(7, 4);
+    assert_eq!(slice, [7, 7, 7, 7]);
+    assert_eq!(slice, cowslice![7; 4]);
+    assert!(CowSlice::from_elem(7, 0).is_empty());
+
+    let full = CowSlice::from([1, 2, 3, 4, 5]);
+    let mut sub = full.slice(1..4);
+    sub.fill(0);
+    assert_eq!(sub, [0, 0, 0]);
+    assert_eq!(full, [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn cow_slice_position() {
+    let full = CowSlice::from([3, 1, 2, 3, 1, 2, 3]);
+    let sub = full.slice(1..6);
+    assert_eq!(sub.position(|&x| x == 3), Some(2));
+    assert_eq!(sub.rposition(|&x| x == 1), Some(3));
+    // Elements outside the view are never found
+    assert_eq!(full.slice(1..3).position(|&x| x == 3), None);
+    assert_eq!(full.slice(1..3).rposition(|&x| x == 3), None);
+}
+
+#[test]
+fn cow_slice_dedup_by() {
+    let full = CowSlice::from([1, 1, 2, 2, 2, 3, 1, 1]);
+    let mut sub = full.slice(1..7);
+    sub.dedup_by(|a, b| a == b);
+    assert_eq!(sub, [1, 2, 3, 1]);
+    assert_eq!(full, [1, 1, 2, 2, 2, 3, 1, 1]);
+
+    let mut slice = CowSlice::from([10, 11, 20, 25, 31]);
+    slice.dedup_by(|a, b| a / 10 == b / 10);
+    assert_eq!(slice, [10, 20, 31]);
+
+    let mut empty = CowSlice::<i32>::new();
+    empty.dedup_by(|a, b| a == b);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn cow_slice_sort() {
+    let full = CowSlice::from([5, 1, 4, 2, 3, 0]);
+    let mut sub = full.slice(1..5);
+    sub.sort();
+    assert_eq!(sub, [1, 2, 3, 4]);
+    assert_eq!(full, [5, 1, 4, 2, 3, 0]);
+
+    let mut slice = full.clone();
+    slice.sort_unstable_by(|a, b| b.cmp(a));
+    assert_eq!(slice, [5, 4, 3, 2, 1, 0]);
+    assert_eq!(full, [5, 1, 4, 2, 3, 0]);
+
+    // Sorting by key only must keep equal keys in their original order
+    let mut pairs = CowSlice::from([(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')]);
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(pairs, [(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+}
+
 impl<T> Default for CowSlice<T> {
     fn default() -> Self {
         Self {
@@ -175,6 +694,46 @@ impl<T: Clone> Clone for CowSlice<T> {
             end: self.end,
         }
     }
+    /// If this slice's buffer is unique and large enough to hold `source`,
+    /// `source` is copied into it so that the buffer can be reused.
+    /// Otherwise, `source`'s buffer is shared, as with [`Clone::clone`].
+    ///
+    /// Copying is slower than sharing unless the slice is then modified,
+    /// so this is for scratch slices that are written after each clone.
+    fn clone_from(&mut self, source: &Self) {
+        if self.data.is_unique() && source.len() <= self.data.capacity() {
+            self.data.clear();
+            self.data.extend_from_slice(source);
+            self.start = 0;
+            self.end = self.data.len();
+        } else {
+            *self = source.clone();
+        }
+    }
+}
+
+#[test]
+fn cow_slice_clone_from() {
+    let source = CowSlice::from([1, 2, 3, 4, 5]);
+    // A unique buffer with room is reused
+    let mut scratch = CowSlice::from([0; 8]);
+    let ptr = scratch.data.as_ptr();
+    scratch.clone_from(&source.slice(1..4));
+    assert_eq!(scratch, [2, 3, 4]);
+    assert_eq!(scratch.data.as_ptr(), ptr);
+    assert!(!scratch.is_copy_of(&source));
+    scratch.as_mut_slice()[0] = 9;
+    assert_eq!(source, [1, 2, 3, 4, 5]);
+    // Too small a buffer shares the source's
+    let mut small = CowSlice::from([0; 2]);
+    small.clone_from(&source);
+    assert!(small.is_copy_of(&source));
+    // So does a shared buffer
+    let mut shared = CowSlice::from([0; 8]);
+    let other = shared.clone();
+    shared.clone_from(&source);
+    assert!(shared.is_copy_of(&source));
+    assert_eq!(other, [0; 8]);
 }
 
 impl<T> Deref for CowSlice<T> {
@@ -338,17 +897,19 @@ impl<'a, T> IntoIterator for &'a CowSlice<T> {
     }
 }
 
+/// This copies a shared buffer up front, like [`CowSlice::iter_mut`]
 impl<'a, T: Clone> IntoIterator for &'a mut CowSlice<T> {
     type Item = &'a mut T;
     type IntoIter = <&'a mut [T] as IntoIterator>::IntoIter;
     fn into_iter(self) -> Self::IntoIter {
-        self.as_mut_slice().iter_mut()
+        self.iter_mut()
     }
 }
 
 impl<T: Clone> FromIterator<T> for CowSlice<T> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut data = EcoVec::new();
+        let iter = iter.into_iter();
+        let mut data = EcoVec::with_capacity(iter.size_hint().0);
         data.extend(iter);
         data.into()
     }
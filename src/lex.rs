@@ -19,36 +19,83 @@ pub fn lex(
     src: impl IntoInputSrc,
     inputs: &mut Inputs,
 ) -> (Vec<Sp<Token>>, Vec<Sp<LexError>>) {
-    let src = inputs.add_src(src, input);
-    Lexer {
-        input_segments: input.graphemes(true).collect(),
-        loc: Loc {
-            char_pos: 0,
-            byte_pos: 0,
-            line: 1,
-            col: 1,
-        },
-        src,
-        tokens: Vec::new(),
-        errors: Vec::new(),
-    }
-    .run()
+    Lexer::new(input, src, inputs, false).run()
+}
+
+/// Lex a Uiua source file, advancing columns to the next multiple of `tab_width` at tabs
+///
+/// [`lex`] counts a tab as a single column, which is the same as a `tab_width` of 1.
+/// A wider tab width lines spans up with editors that render tabs as multiple columns.
+/// Byte and character positions are unaffected.
+///
+/// # Panics
+/// Panics if `tab_width` is 0
+pub fn lex_with_tab_width(
+    input: &str,
+    tab_width: u16,
+    src: impl IntoInputSrc,
+    inputs: &mut Inputs,
+) -> (Vec<Sp<Token>>, Vec<Sp<LexError>>) {
+    assert!(tab_width > 0, "tab width must be positive");
+    let mut lexer = Lexer::new(input, src, inputs, false);
+    lexer.tab_width = tab_width;
+    lexer.run()
+}
+
+/// Lex a Uiua source file that has already been split into grapheme clusters
+///
+/// `graphemes` must be `input`'s extended grapheme clusters, in order,
+/// as given by [`UnicodeSegmentation::graphemes`].
+/// Span byte positions are computed from the graphemes, so they are
+/// only correct if the graphemes concatenate to `input`.
+pub fn lex_graphemes<'a>(
+    input: &'a str,
+    graphemes: Vec<&'a str>,
+    src: impl IntoInputSrc,
+    inputs: &mut Inputs,
+) -> (Vec<Sp<Token>>, Vec<Sp<LexError>>) {
+    debug_assert!(
+        graphemes.iter().map(|g| g.len()).sum::<usize>() == input.len(),
+        "graphemes do not make up the input"
+    );
+    Lexer::with_graphemes(input, graphemes, src, inputs, false).run()
+}
+
+/// Lex a Uiua source file, decomposing numbers into their components
+///
+/// Numbers are emitted as [`Token::NumberParts`] instead of [`Token::Number`].
+/// In this mode, `0x`-prefixed hexadecimal literals are also recognized.
+/// Ratio literals like `1/3` have no decomposition and are still emitted as [`Token::Number`].
+pub fn lex_structured_numbers(
+    input: &str,
+    src: impl IntoInputSrc,
+    inputs: &mut Inputs,
+) -> (Vec<Sp<Token>>, Vec<Sp<LexError>>) {
+    Lexer::new(input, src, inputs, true).run()
 }
 
 /// An error that occurred while lexing
 #[allow(missing_docs)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LexError {
-    UnexpectedChar(String),
+    UnexpectedChars(String),
     ExpectedCharacter(Vec<char>),
     InvalidEscape(String),
     ExpectedNumber,
+    AsciiMinusExponent,
+    /// A string with no closing quote, with the span of its opening quote
+    UnterminatedString(CodeSpan),
+    /// A `@` with no character after it, with the span of the `@`
+    UnterminatedChar(CodeSpan),
 }
 
 impl fmt::Display for LexError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            LexError::UnexpectedChar(c) => write!(f, "Unexpected char {c:?}"),
+            LexError::UnexpectedChars(s) if s.chars().count() == 1 => {
+                write!(f, "Unexpected char {s:?}")
+            }
+            LexError::UnexpectedChars(s) => write!(f, "Unexpected chars {s:?}"),
             LexError::ExpectedCharacter(chars) if chars.is_empty() => {
                 write!(f, "Expected character")
             }
@@ -60,7 +107,14 @@ impl fmt::Display for LexError {
             }
             LexError::ExpectedCharacter(chars) => write!(f, "Expected one of {:?}", chars),
             LexError::InvalidEscape(c) => write!(f, "Invalid escape character {c:?}"),
+            LexError::UnterminatedString(opening) => {
+                write!(f, "Unterminated string starting at {}", opening.start)
+            }
+            LexError::UnterminatedChar(_) => write!(f, "Expected character after @"),
             LexError::ExpectedNumber => write!(f, "Expected number"),
+            LexError::AsciiMinusExponent => {
+                write!(f, "Negative exponents are written with ¯ or `, not -")
+            }
         }
     }
 }
@@ -85,6 +139,49 @@ impl fmt::Display for Loc {
     }
 }
 
+impl Loc {
+    /// Get the location after a character
+    ///
+    /// The character counts as one toward [`Loc::char_pos`], so it should not
+    /// be part of a larger grapheme cluster. Use [`Loc::advance_str`] for those.
+    pub fn advance(mut self, c: char) -> Loc {
+        self.advance_with_tab_width(c.encode_utf8(&mut [0; 4]), 1);
+        self
+    }
+    /// Get the location after some text
+    ///
+    /// Characters are counted as grapheme clusters, the same as [`Loc::char_pos`].
+    pub fn advance_str(mut self, s: &str) -> Loc {
+        for segment in s.graphemes(true) {
+            self.advance_with_tab_width(segment, 1);
+        }
+        self
+    }
+    /// Advance the location past a grapheme of source text,
+    /// moving to the next tab stop at tabs
+    fn advance_with_tab_width(&mut self, segment: &str, tab_width: u16) {
+        for c in segment.chars() {
+            match c {
+                '\n' => {
+                    self.line += 1;
+                    self.col = 1;
+                }
+                '\r' => {}
+                '\t' => self.col = (self.col - 1) / tab_width * tab_width + tab_width + 1,
+                _ => self.col += 1,
+            }
+        }
+        self.char_pos += 1;
+        self.byte_pos += segment.len() as u32;
+    }
+    /// Display the location along with its character and byte positions
+    ///
+    /// The format is `line:col (char N, byte M)`.
+    pub fn display_full(&self) -> String {
+        format!("{self} (char {}, byte {})", self.char_pos, self.byte_pos)
+    }
+}
+
 impl Default for Loc {
     fn default() -> Self {
         Self {
@@ -262,14 +359,21 @@ impl CodeSpan {
         Sp { value, span: self }
     }
     /// Merge two spans
+    ///
+    /// Both spans must come from the same source.
+    /// This is checked in debug builds.
     pub fn merge(self, end: Self) -> Self {
+        debug_assert_eq!(
+            self.src, end.src,
+            "cannot merge spans from different sources"
+        );
         CodeSpan {
             start: self.start.min(end.start),
             end: self.end.max(end.end),
             ..self
         }
     }
-    /// Get the text of the span
+    /// Get the range of byte offsets of the span in its source
     pub fn byte_range(&self) -> Range<usize> {
         self.start.byte_pos as usize..self.end.byte_pos as usize
     }
@@ -285,6 +389,25 @@ impl CodeSpan {
                 && (self.end.line > line || col <= self.end.col)
         }
     }
+    /// Get the span of a range of characters within this span
+    ///
+    /// Characters are counted as grapheme clusters, the same as [`Loc::char_pos`].
+    pub fn substring(&self, char_range: Range<usize>, inputs: &Inputs) -> Self {
+        self.as_str(inputs, |s| self.substring_of(s, char_range))
+    }
+    /// Get the span of a range of characters within this span, given the span's text
+    pub(crate) fn substring_of(&self, text: &str, char_range: Range<usize>) -> Self {
+        let mut segments = text.graphemes(true);
+        let start = (segments.by_ref().take(char_range.start)).fold(self.start, Loc::advance_str);
+        let end = segments
+            .take(char_range.len())
+            .fold(start, Loc::advance_str);
+        CodeSpan {
+            start,
+            end,
+            src: self.src.clone(),
+        }
+    }
     /// Get the text of the span from the inputs
     pub fn as_str<T>(&self, inputs: &Inputs, f: impl FnOnce(&str) -> T) -> T {
         inputs.get_with(&self.src, |input| f(&input[self.byte_range()]))
@@ -402,7 +525,14 @@ pub enum Token {
     Comment,
     OutputComment(usize),
     Ident,
-    Number,
+    Number(NumberFormat),
+    NumberParts {
+        negative: bool,
+        base: u32,
+        int: String,
+        frac: String,
+        exp: Option<i32>,
+    },
     Char(String),
     Str(String),
     FormatStr(Vec<String>),
@@ -414,6 +544,26 @@ pub enum Token {
     Spaces,
 }
 
+/// Hints about how a number literal was spelled
+///
+/// The digits themselves can be recovered from the token's span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// The radix the number was written in
+    pub radix: u32,
+    /// Whether the number was written with an exponent
+    pub exponent: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            radix: 10,
+            exponent: false,
+        }
+    }
+}
+
 impl Token {
     pub(crate) fn as_char(&self) -> Option<String> {
         match self {
@@ -516,25 +666,48 @@ struct Lexer<'a> {
     src: InputSrc,
     tokens: Vec<Sp<Token>>,
     errors: Vec<Sp<LexError>>,
+    structured_numbers: bool,
+    tab_width: u16,
 }
 
 impl<'a> Lexer<'a> {
+    fn new(
+        input: &'a str,
+        src: impl IntoInputSrc,
+        inputs: &mut Inputs,
+        structured_numbers: bool,
+    ) -> Self {
+        let graphemes = input.graphemes(true).collect();
+        Self::with_graphemes(input, graphemes, src, inputs, structured_numbers)
+    }
+    fn with_graphemes(
+        input: &'a str,
+        graphemes: Vec<&'a str>,
+        src: impl IntoInputSrc,
+        inputs: &mut Inputs,
+        structured_numbers: bool,
+    ) -> Self {
+        let src = inputs.add_src(src, input);
+        Lexer {
+            input_segments: graphemes,
+            loc: Loc {
+                char_pos: 0,
+                byte_pos: 0,
+                line: 1,
+                col: 1,
+            },
+            src,
+            tokens: Vec::new(),
+            errors: Vec::new(),
+            structured_numbers,
+            tab_width: 1,
+        }
+    }
     fn peek_char(&self) -> Option<&'a str> {
         self.input_segments.get(self.loc.char_pos as usize).copied()
     }
     fn update_loc(&mut self, c: &'a str) {
-        for c in c.chars() {
-            match c {
-                '\n' => {
-                    self.loc.line += 1;
-                    self.loc.col = 1;
-                }
-                '\r' => {}
-                _ => self.loc.col += 1,
-            }
-        }
-        self.loc.char_pos += 1;
-        self.loc.byte_pos += c.len() as u32;
+        self.loc.advance_with_tab_width(c, self.tab_width);
     }
     fn next_char_if(&mut self, f: impl Fn(&str) -> bool) -> Option<&'a str> {
         let c = *self.input_segments.get(self.loc.char_pos as usize)?;
@@ -622,19 +795,19 @@ impl<'a> Lexer<'a> {
                 "'" if self.next_char_exact("'") => self.end(Quote2, start),
                 "'" => self.end(Quote, start),
                 "`" => {
-                    if self.number("-") {
-                        self.end(Number, start)
+                    if let Some(format) = self.number("-") {
+                        self.end_number(start, format)
                     } else {
                         self.end(Backtick, start)
                     }
                 }
-                "¯" if self
-                    .peek_char()
-                    .filter(|c| c.chars().all(|c| c.is_ascii_digit()))
-                    .is_some() =>
-                {
-                    self.number("-");
-                    self.end(Number, start)
+                // A lone `¯` is the glyph for negate
+                "¯" => {
+                    if let Some(format) = self.number("-") {
+                        self.end_number(start, format)
+                    } else {
+                        self.end(Glyph(Primitive::Neg), start)
+                    }
                 }
                 "*" => self.end(Star, start),
                 "%" => self.end(Percent, start),
@@ -680,12 +853,13 @@ impl<'a> Lexer<'a> {
                 }
                 // Characters
                 "@" => {
+                    let at = self.make_span(start, self.loc);
                     let mut escaped = false;
-                    let char = match self.character(&mut escaped, None) {
+                    let char = match self.character(&mut escaped, None, false) {
                         Ok(Some(c)) => c,
                         Ok(None) => {
                             self.errors
-                                .push(self.end_span(start).sp(LexError::ExpectedCharacter(vec![])));
+                                .push(self.end_span(start).sp(LexError::UnterminatedChar(at)));
                             continue;
                         }
                         Err(e) => {
@@ -706,7 +880,7 @@ impl<'a> Lexer<'a> {
                         // Multiline strings
                         let mut start = start;
                         loop {
-                            let inner = self.parse_string_contents(start, None);
+                            let inner = self.parse_string_contents(None, true);
                             let string = parse_format_fragments(&inner);
                             self.end(MultilineString(string), start);
                             let checkpoint = self.loc;
@@ -737,11 +911,12 @@ impl<'a> Lexer<'a> {
                         errored = true;
                     }
                     // Single-line strings
-                    let inner = self.parse_string_contents(start, Some('"'));
+                    let opening = self.make_span(start, self.loc);
+                    let inner = self.parse_string_contents(Some('"'), format);
                     if !self.next_char_exact("\"") && !errored {
                         self.errors.push(
                             self.end_span(start)
-                                .sp(LexError::ExpectedCharacter(vec!['"'])),
+                                .sp(LexError::UnterminatedString(opening)),
                         );
                     }
                     if format {
@@ -760,6 +935,7 @@ impl<'a> Lexer<'a> {
                         ident.push_str(c);
                     }
                     let mut exclam_count = 0;
+                    let before_exclams = self.loc;
                     while self.next_char_exact("!") {
                         ident.push('!');
                         exclam_count += 1;
@@ -777,26 +953,25 @@ impl<'a> Lexer<'a> {
                     if let Some(prims) = Primitive::from_format_name_multi(&ident[..lowercase_end])
                     {
                         if ambiguous_ne {
-                            self.loc.char_pos -= 1;
-                            self.loc.byte_pos -= 1;
+                            // Leave the `!` to be lexed with the `=`
+                            self.loc = before_exclams;
                         }
-                        let mut start = start;
+                        let ident_span = self.end_span(start);
+                        let mut frag_start = ident_span.start;
                         for (prim, frag) in prims {
-                            let end = Loc {
-                                col: start.col + frag.chars().count() as u16,
-                                char_pos: start.char_pos + frag.chars().count() as u32,
-                                byte_pos: start.byte_pos + frag.len() as u32,
-                                ..start
-                            };
+                            let frag_end = frag_start.advance_str(frag);
                             self.tokens.push(Sp {
                                 value: Glyph(prim),
-                                span: self.make_span(start, end),
+                                span: CodeSpan {
+                                    start: frag_start,
+                                    end: frag_end,
+                                    src: ident_span.src.clone(),
+                                },
                             });
-                            start = end;
+                            frag_start = frag_end;
                         }
-                        let rest = &ident[lowercase_end..];
-                        if !rest.is_empty() {
-                            self.end(Ident, start);
+                        if lowercase_end < ident.len() {
+                            self.end(Ident, frag_start);
                         }
                     } else {
                         // Lone ident
@@ -805,8 +980,8 @@ impl<'a> Lexer<'a> {
                 }
                 // Numbers
                 c if c.chars().all(|c| c.is_ascii_digit()) => {
-                    self.number(c);
-                    self.end(Number, start)
+                    let format = self.number(c).unwrap_or_default();
+                    self.end_number(start, format)
                 }
                 // Newlines
                 "\n" | "\r\n" => {
@@ -830,14 +1005,53 @@ impl<'a> Lexer<'a> {
                             continue;
                         }
                     }
+                    // Contiguous unexpected chars are reported together
+                    let span = self.end_span(start);
+                    if let Some(Sp {
+                        value: LexError::UnexpectedChars(chars),
+                        span: prev,
+                    }) = self.errors.last_mut()
+                    {
+                        if prev.end == span.start {
+                            chars.push_str(c);
+                            *prev = prev.clone().merge(span);
+                            continue;
+                        }
+                    }
                     self.errors
-                        .push(self.end_span(start).sp(LexError::UnexpectedChar(c.into())));
+                        .push(span.sp(LexError::UnexpectedChars(c.into())));
                 }
             };
         }
         (self.tokens, self.errors)
     }
-    fn number(&mut self, init: &str) -> bool {
+    fn end_number(&mut self, start: Loc, format: NumberFormat) {
+        if self.structured_numbers {
+            let text =
+                self.input_segments[start.char_pos as usize..self.loc.char_pos as usize].concat();
+            if let Some(parts) = number_parts(&text) {
+                self.end(parts, start);
+                return;
+            }
+        }
+        self.end(Token::Number(format), start)
+    }
+    fn number(&mut self, init: &str) -> Option<NumberFormat> {
+        let mut format = NumberFormat::default();
+        // Hexadecimal
+        if self.structured_numbers {
+            let start = self.loc;
+            let zero = init == "0" || init == "-" && self.next_char_exact("0");
+            if zero
+                && self.next_char_exact("x")
+                && self.next_char_if_all(|c| c.is_ascii_hexdigit()).is_some()
+            {
+                while self.next_char_if_all(|c| c.is_ascii_hexdigit()).is_some() {}
+                format.radix = 16;
+                return Some(format);
+            }
+            self.loc = start;
+        }
         // Whole part
         let mut got_digit = false;
         while self
@@ -847,7 +1061,7 @@ impl<'a> Lexer<'a> {
             got_digit = true;
         }
         if !init.chars().all(|c| c.is_ascii_digit()) && !got_digit {
-            return false;
+            return None;
         }
         // Fractional part
         let before_dot = self.loc;
@@ -880,7 +1094,20 @@ impl<'a> Lexer<'a> {
         // Exponent
         let loc_before_e = self.loc;
         if !fractional && self.next_char_if(|c| c == "e" || c == "E").is_some() {
-            self.next_char_if(|c| c == "-" || c == "`" || c == "¯");
+            // Exponents take the same signs as the number itself.
+            // `-` is always subtract, but it is still consumed here so that
+            // `1e-3` is reported rather than silently lexed as `1 e - 3`.
+            let before_sign = self.loc;
+            if self.next_char_exact("-") {
+                if (self.peek_char()).is_some_and(|c| c.chars().all(|c| c.is_ascii_digit())) {
+                    let span = self.make_span(before_sign, self.loc);
+                    self.errors.push(span.sp(LexError::AsciiMinusExponent));
+                } else {
+                    self.loc = before_sign;
+                }
+            } else {
+                self.next_char_if(|c| c == "`" || c == "¯");
+            }
             let mut got_digit = false;
             while self
                 .next_char_if(|c| c.chars().all(|c| c.is_ascii_digit()))
@@ -888,16 +1115,23 @@ impl<'a> Lexer<'a> {
             {
                 got_digit = true;
             }
-            if !got_digit {
+            if got_digit {
+                format.exponent = true;
+            } else {
                 self.loc = loc_before_e;
             }
         }
-        true
+        Some(format)
     }
+    /// Lex a character of a char or string literal
+    ///
+    /// In format strings, an escaped underscore is replaced with [`char::MAX`]
+    /// so that it is not mistaken for a placeholder.
     fn character(
         &mut self,
         escaped: &mut bool,
         escape_char: Option<char>,
+        format: bool,
     ) -> Result<Option<String>, &'a str> {
         let Some(c) =
             self.next_char_if_all(|c| !"\r\n".contains(c) && (Some(c) != escape_char || *escaped))
@@ -916,46 +1150,53 @@ impl<'a> Lexer<'a> {
                 "\\" => '\\'.to_string(),
                 "\"" => '"'.to_string(),
                 "'" => '\''.to_string(),
-                "_" => char::MAX.to_string(),
-                "x" => {
-                    let mut code = 0;
-                    for _ in 0..2 {
-                        let c = self
-                            .next_char_if_all(|c| c.is_ascii_hexdigit())
-                            .ok_or("x")?;
-                        code = code << 4 | c.chars().next().unwrap().to_digit(16).unwrap();
-                    }
-                    std::char::from_u32(code).ok_or("x")?.into()
-                }
-                "u" => {
-                    let mut code = 0;
-                    for _ in 0..4 {
-                        let c = self
-                            .next_char_if_all(|c| c.is_ascii_hexdigit())
-                            .ok_or("u")?;
-                        code = code << 4 | c.chars().next().unwrap().to_digit(16).unwrap();
-                    }
-                    std::char::from_u32(code).ok_or("u")?.into()
-                }
+                "_" if format => char::MAX.to_string(),
+                "_" => '_'.to_string(),
+                // `\xNN` is the character with code point `NN`,
+                // so `\x80` through `\xff` are the Latin-1 characters
+                "x" => self.hex_escape("x", 2, format)?,
+                "u" => self.hex_escape("u", 4, format)?,
                 c => return Err(c),
             }
         } else if c == "\\" {
             *escaped = true;
-            return self.character(escaped, escape_char);
+            return self.character(escaped, escape_char, format);
         } else {
             c.into()
         }))
     }
-    fn parse_string_contents(&mut self, start: Loc, escape_char: Option<char>) -> String {
+    /// Parse the hex digits of an escape into the character with that code point
+    fn hex_escape(
+        &mut self,
+        escape: &'a str,
+        digits: usize,
+        format: bool,
+    ) -> Result<String, &'a str> {
+        let mut code = 0;
+        for _ in 0..digits {
+            let c = (self.next_char_if_all(|c| c.is_ascii_hexdigit())).ok_or(escape)?;
+            code = code << 4 | c.chars().next().unwrap().to_digit(16).unwrap();
+        }
+        Ok(match std::char::from_u32(code).ok_or(escape)? {
+            // An escaped underscore is never a format placeholder
+            '_' if format => char::MAX.to_string(),
+            c => c.into(),
+        })
+    }
+    fn parse_string_contents(&mut self, escape_char: Option<char>, format: bool) -> String {
         let mut string = String::new();
         let mut escaped = false;
         loop {
-            match self.character(&mut escaped, escape_char) {
+            // Errors only span the offending escape, not the whole string
+            let char_start = self.loc;
+            match self.character(&mut escaped, escape_char, format) {
                 Ok(Some(c)) => string.push_str(&c),
                 Ok(None) => break,
                 Err(e) => {
-                    self.errors
-                        .push(self.end_span(start).sp(LexError::InvalidEscape(e.into())));
+                    self.errors.push(
+                        self.end_span(char_start)
+                            .sp(LexError::InvalidEscape(e.into())),
+                    );
                 }
             }
         }
@@ -963,6 +1204,42 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// Decompose the text of a number token into its components
+fn number_parts(s: &str) -> Option<Token> {
+    let (negative, s) = match s.strip_prefix(['`', '¯']) {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    if let Some(hex) = s.strip_prefix("0x") {
+        return Some(Token::NumberParts {
+            negative,
+            base: 16,
+            int: hex.into(),
+            frac: String::new(),
+            exp: None,
+        });
+    }
+    if s.contains('/') {
+        return None;
+    }
+    let (mantissa, exp) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exp)) => (mantissa, Some(exp.replace(['`', '¯'], "-").parse().ok()?)),
+        None => (s, None),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    Some(Token::NumberParts {
+        negative,
+        base: 10,
+        int: int.into(),
+        frac: frac.into(),
+        exp,
+    })
+}
+
+/// Split the contents of a format string at its `_` placeholders
+///
+/// A literal underscore is written `\_` (or `\x5f`), which
+/// [`Lexer::character`] has already replaced with [`char::MAX`].
 fn parse_format_fragments(s: &str) -> Vec<String> {
     let mut frags: Vec<String> = Vec::new();
     let mut curr = String::new();
@@ -998,3 +1275,356 @@ pub fn is_custom_glyph(c: &str) -> bool {
             .all(|c| !c.is_ascii() && !is_ident_char(c) && Primitive::from_glyph(c).is_none()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex_number(input: &str) -> Token {
+        let (tokens, errors) = lex_structured_numbers(input, (), &mut Inputs::default());
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(tokens.len(), 1, "{tokens:?}");
+        tokens.into_iter().next().unwrap().value
+    }
+
+    #[test]
+    fn structured_numbers() {
+        assert_eq!(
+            lex_number("¯12.34e5"),
+            Token::NumberParts {
+                negative: true,
+                base: 10,
+                int: "12".into(),
+                frac: "34".into(),
+                exp: Some(5),
+            }
+        );
+        assert_eq!(
+            lex_number("0x1F"),
+            Token::NumberParts {
+                negative: false,
+                base: 16,
+                int: "1F".into(),
+                frac: String::new(),
+                exp: None,
+            }
+        );
+        assert_eq!(
+            lex_number("`0xff"),
+            Token::NumberParts {
+                negative: true,
+                base: 16,
+                int: "ff".into(),
+                frac: String::new(),
+                exp: None,
+            }
+        );
+        assert_eq!(
+            lex_number("3e¯2"),
+            Token::NumberParts {
+                negative: false,
+                base: 10,
+                int: "3".into(),
+                frac: String::new(),
+                exp: Some(-2),
+            }
+        );
+        assert_eq!(lex_number("1/3"), Token::Number(NumberFormat::default()));
+    }
+
+    #[test]
+    fn byte_positions_with_multibyte_chars() {
+        let input = "\"a😀b\" 5 \"😀\\q\"";
+        let (tokens, errors) = lex(input, (), &mut Inputs::default());
+        let texts: Vec<&str> = (tokens.iter())
+            .filter(|token| !matches!(token.value, Token::Spaces))
+            .map(|token| &input[token.span.byte_range()])
+            .collect();
+        assert_eq!(texts, ["\"a😀b\"", "5", "\"😀\\q\""]);
+        let five = &tokens[2].span;
+        assert_eq!(five.start.display_full(), "1:7 (char 6, byte 9)");
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert_eq!(&input[errors[0].span.byte_range()], "\\q");
+    }
+
+    #[test]
+    fn ambiguous_not_equals_spans() {
+        let input = "⇌ revrev!=2";
+        let (tokens, errors) = lex(input, (), &mut Inputs::default());
+        assert!(errors.is_empty(), "{errors:?}");
+        let spans: Vec<(&str, String)> = (tokens.iter())
+            .filter(|token| !matches!(token.value, Token::Spaces))
+            .map(|token| {
+                let span = &token.span;
+                (&input[span.byte_range()], span.end.display_full())
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("⇌", "1:2 (char 1, byte 3)".into()),
+                ("rev", "1:6 (char 5, byte 7)".into()),
+                ("rev", "1:9 (char 8, byte 10)".into()),
+                ("!=", "1:11 (char 10, byte 12)".into()),
+                ("2", "1:12 (char 11, byte 13)".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unexpected_char_runs() {
+        let input = "1 \u{1}\u{2}\u{3} 2 \u{7f} \u{1}";
+        let (tokens, errors) = lex(input, (), &mut Inputs::default());
+        let errors: Vec<(&LexError, &str)> = (errors.iter())
+            .map(|err| (&err.value, &input[err.span.byte_range()]))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (
+                    &LexError::UnexpectedChars("\u{1}\u{2}\u{3}".into()),
+                    "\u{1}\u{2}\u{3}"
+                ),
+                (&LexError::UnexpectedChars("\u{7f}".into()), "\u{7f}"),
+                (&LexError::UnexpectedChars("\u{1}".into()), "\u{1}"),
+            ]
+        );
+        assert_eq!(
+            errors[0].0.to_string(),
+            r#"Unexpected chars "\u{1}\u{2}\u{3}""#
+        );
+        assert_eq!(errors[1].0.to_string(), r#"Unexpected char "\u{7f}""#);
+        // Tokens after a run are still lexed
+        let numbers = (tokens.iter())
+            .filter(|token| matches!(token.value, Token::Number(_)))
+            .count();
+        assert_eq!(numbers, 2);
+    }
+
+    #[test]
+    fn number_formats() {
+        let (tokens, errors) = lex("5 ¯2.5e3 1E2 3e", (), &mut Inputs::default());
+        assert!(errors.is_empty(), "{errors:?}");
+        let formats: Vec<NumberFormat> = (tokens.into_iter())
+            .filter_map(|token| match token.value {
+                Token::Number(format) => Some(format),
+                _ => None,
+            })
+            .collect();
+        let plain = NumberFormat::default();
+        let exponent = NumberFormat {
+            exponent: true,
+            ..plain
+        };
+        assert_eq!(formats, [plain, exponent, exponent, plain]);
+    }
+
+    #[test]
+    fn lex_graphemes_matches_lex() {
+        let input = "⇌ \"a😀b\" # comment\n+ 1 2";
+        let graphemes = input.graphemes(true).collect();
+        let (tokens, errors) = lex(input, (), &mut Inputs::default());
+        let (grapheme_tokens, grapheme_errors) =
+            lex_graphemes(input, graphemes, (), &mut Inputs::default());
+        assert_eq!(errors, grapheme_errors);
+        assert_eq!(tokens.len(), grapheme_tokens.len());
+        for (a, b) in tokens.iter().zip(&grapheme_tokens) {
+            assert_eq!(a.value, b.value);
+            assert_eq!(a.span.byte_range(), b.span.byte_range());
+        }
+    }
+
+    #[test]
+    fn tab_width_columns() {
+        let plus_col = |input: &str, tab_width: u16| {
+            let (tokens, errors) = lex_with_tab_width(input, tab_width, (), &mut Inputs::default());
+            assert!(errors.is_empty(), "{errors:?}");
+            let plus = tokens
+                .iter()
+                .find(|t| t.value == Token::Glyph(Primitive::Add));
+            let span = &plus.unwrap().span;
+            (span.start.line, span.start.col, span.start.char_pos)
+        };
+        assert_eq!(plus_col("\t+", 1), (1, 2, 1));
+        assert_eq!(plus_col("\t+", 4), (1, 5, 1));
+        assert_eq!(plus_col("ab\t+", 4), (1, 5, 3));
+        assert_eq!(plus_col("abcd\t+", 4), (1, 9, 5));
+        assert_eq!(plus_col("\t\t+", 4), (1, 9, 2));
+        assert_eq!(plus_col("1\n\t+", 8), (2, 9, 3));
+        let (tokens, _) = lex("\t+", (), &mut Inputs::default());
+        assert_eq!(tokens.last().unwrap().span.start.col, 2);
+    }
+
+    #[test]
+    #[should_panic = "cannot merge spans from different sources"]
+    #[cfg(debug_assertions)]
+    fn merge_spans_from_different_sources() {
+        let mut inputs = Inputs::default();
+        let (a, _) = lex("1", (), &mut inputs);
+        let (b, _) = lex("2", (), &mut inputs);
+        a[0].span.clone().merge(b[0].span.clone());
+    }
+
+    #[test]
+    fn lone_negative_signs() {
+        let (tokens, errors) = lex("¯ `x ¯3 `3", (), &mut Inputs::default());
+        assert!(errors.is_empty(), "{errors:?}");
+        let tokens: Vec<Token> = (tokens.into_iter())
+            .map(|token| token.value)
+            .filter(|token| !matches!(token, Token::Spaces))
+            .collect();
+        assert!(matches!(tokens[0], Token::Glyph(Primitive::Neg)));
+        assert!(matches!(tokens[1], Token::Simple(AsciiToken::Backtick)));
+        assert!(matches!(tokens[3], Token::Number(_)));
+        assert!(matches!(tokens[4], Token::Number(_)));
+        assert_eq!(tokens.len(), 5);
+    }
+
+    #[test]
+    fn number_signs() {
+        let lex_texts = |input: &str| {
+            let (tokens, errors) = lex(input, (), &mut Inputs::default());
+            let texts: Vec<String> = (tokens.iter())
+                .map(|token| input[token.span.byte_range()].to_string())
+                .collect();
+            (texts, errors)
+        };
+        // `¯` and backtick are signs in both the mantissa and the exponent
+        for mantissa in ["¯", "`", ""] {
+            for exp in ["¯", "`", ""] {
+                let input = format!("{mantissa}1.5e{exp}3");
+                let (texts, errors) = lex_texts(&input);
+                assert!(errors.is_empty(), "{input}: {errors:?}");
+                assert_eq!(texts, [input.as_str()]);
+            }
+        }
+        // `-` is subtract before a number
+        let (texts, errors) = lex_texts("-1.5");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(texts, ["-", "1.5"]);
+        // ...and an error in an exponent
+        let (texts, errors) = lex_texts("¯1.5e-3");
+        assert_eq!(texts, ["¯1.5e-3"]);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(matches!(errors[0].value, LexError::AsciiMinusExponent));
+        // A `-` that does not start an exponent is left alone
+        let (texts, errors) = lex_texts("2e-x");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(texts[0], "2");
+    }
+
+    #[test]
+    fn substring_spans() {
+        let input = "\"a\tb😀c\"";
+        let mut inputs = Inputs::default();
+        let (tokens, _) = lex(input, (), &mut inputs);
+        let span = &tokens[0].span;
+        let sub = span.substring(4..6, &inputs);
+        assert_eq!(&input[sub.byte_range()], "😀c");
+        assert_eq!((sub.start.col, sub.start.char_pos), (5, 4));
+        assert_eq!((sub.end.col, sub.end.char_pos), (7, 6));
+
+        // Glyphs split from a formatted name get their own spans
+        let input = "revfirst";
+        let (tokens, _) = lex(input, (), &mut Inputs::default());
+        let texts: Vec<&str> = (tokens.iter())
+            .map(|token| &input[token.span.byte_range()])
+            .collect();
+        assert_eq!(texts, ["rev", "first"]);
+        assert_eq!(tokens[1].span.start.col, 4);
+    }
+
+    #[test]
+    fn hex_escapes() {
+        let lex_one = |input: &str| {
+            let (tokens, errors) = lex(input, (), &mut Inputs::default());
+            assert_eq!(tokens.len(), 1, "{tokens:?}");
+            (tokens.into_iter().next().unwrap().value, errors)
+        };
+        assert_eq!(lex_one("@\\x41").0, Token::Char("A".into()));
+        assert_eq!(lex_one("\"\\x41\\xe9\"").0, Token::Str("Aé".into()));
+        assert_eq!(
+            lex_one("$\"a\\x09_b\"").0,
+            Token::FormatStr(vec!["a\t".into(), "b".into()])
+        );
+        assert_eq!(
+            lex_one("$\"a\\x5f_\"").0,
+            Token::FormatStr(vec!["a_".into(), "".into()])
+        );
+        assert_eq!(
+            lex_one("$\"\\_a_\\x5f\\__\"").0,
+            Token::FormatStr(vec!["_a".into(), "__".into(), "".into()])
+        );
+        // Outside of format strings, an escaped underscore is just an underscore
+        assert_eq!(lex_one("\"\\x5f\"").0, Token::Str("_".into()));
+        assert_eq!(lex_one("\"a\\u005fb\"").0, Token::Str("a_b".into()));
+        assert_eq!(lex_one("@\\x5f").0, Token::Char("_".into()));
+        assert_eq!(lex_one("@\\x5f").0, lex_one("@_").0);
+        assert_eq!(lex_one("\"\\_\"").0, Token::Str("_".into()));
+        assert_eq!(lex_one("@\\_").0, Token::Char("_".into()));
+        let (_, errors) = lex_one("\"\\x4g\"");
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(matches!(&errors[0].value, LexError::InvalidEscape(e) if e == "x"));
+    }
+
+    #[test]
+    fn escape_error_spans() {
+        let input = r#"$"long _ format \q string _ \x4g" "plain \z""#;
+        let (_, errors) = lex(input, (), &mut Inputs::default());
+        let texts: Vec<&str> = (errors.iter())
+            .map(|error| &input[error.span.byte_range()])
+            .collect();
+        assert_eq!(texts, [r"\q", r"\x4", r"\z"], "{errors:?}");
+    }
+
+    #[test]
+    fn char_literals() {
+        let (tokens, errors) = lex("@a@ @\\n@'", (), &mut Inputs::default());
+        assert!(errors.is_empty(), "{errors:?}");
+        let chars: Vec<Token> = tokens.into_iter().map(|token| token.value).collect();
+        assert_eq!(chars, ["a", " ", "\n", "'"].map(|c| Token::Char(c.into())));
+        let (_, errors) = lex("@", (), &mut Inputs::default());
+        assert!(matches!(&errors[..], [e] if matches!(e.value, LexError::UnterminatedChar(_))));
+    }
+
+    #[test]
+    fn loc_advance() {
+        let start = Loc::default();
+        let loc = start.advance('a').advance('⊂');
+        assert_eq!(
+            (loc.line, loc.col, loc.char_pos, loc.byte_pos),
+            (1, 3, 2, 4)
+        );
+        let loc = loc.advance('\n');
+        assert_eq!(
+            (loc.line, loc.col, loc.char_pos, loc.byte_pos),
+            (2, 1, 3, 5)
+        );
+        assert_eq!(start.advance_str("a⊂\n"), loc);
+        // Grapheme clusters count as one character
+        let loc = start.advance_str("e\u{301}x");
+        assert_eq!((loc.col, loc.char_pos, loc.byte_pos), (4, 2, 4));
+    }
+
+    #[test]
+    fn unterminated_literals() {
+        let opening_text = |input: &str| {
+            let (_, errors) = lex(input, (), &mut Inputs::default());
+            assert_eq!(errors.len(), 1, "{input:?}: {errors:?}");
+            let (LexError::UnterminatedString(opening) | LexError::UnterminatedChar(opening)) =
+                &errors[0].value
+            else {
+                panic!("{input:?}: {errors:?}");
+            };
+            input[opening.byte_range()].to_string()
+        };
+        // End of input in a string
+        assert_eq!(opening_text("1 \"abc"), "\"");
+        assert_eq!(opening_text("1 $\"a_c"), "$\"");
+        // End of line in a string
+        assert_eq!(opening_text("\"abc\n2"), "\"");
+        // End of line after a character literal
+        assert_eq!(opening_text("@\n2"), "@");
+        assert_eq!(opening_text("1 @"), "@");
+    }
+}
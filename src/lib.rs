@@ -127,6 +127,7 @@ mod sys_native;
 mod value;
 
 pub use self::{
+    algorithm::{CountsMode, OobMode, RotateMode},
     array::*,
     assembly::*,
     boxed::*,
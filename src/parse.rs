@@ -791,7 +791,9 @@ impl<'i> Parser<'i> {
         })
     }
     fn try_num(&mut self) -> Option<Sp<(String, f64)>> {
-        let span = self.try_exact(Token::Number)?;
+        let span = self
+            .next_token_map(|t| matches!(t, Token::Number(_)).then_some(()))?
+            .span;
         let s = self.input[span.byte_range()].to_string();
         fn parse(s: &str) -> Option<f64> {
             let parseable = s.replace(['`', '¯'], "-");
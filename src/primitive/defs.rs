@@ -831,6 +831,8 @@ primitive!(
     /// ex: ↯3_¯1_5 ⇡30
     /// If [fill] is used, the total number of elements in the new shape will always be `greater or equal` the total number of elements in the original shape.
     /// ex: ⬚0↯ ¯1_5 ⇡12
+    /// With [fill], the first and last dimensions may both be negative. The last is derived from the length of the original array's rows, and then the first is derived from the total number of elements. Both are rounded up.
+    /// ex: ⬚0↯ ¯1_2_¯1 ↯2_7⇡14
     ///
     /// See also: [deshape]
     (2, Reshape, DyadicArray, ("reshape", '↯')),
@@ -93,6 +93,30 @@ Life ← ↥⊙↧∩=3,2-,/+≡↻☇1-1⇡3_3¤.
 ≡(-⊃∠(-π◿τ+⍜∩°√+))∩¤ # Generate
 <0.2⌵                # Threshold",
         ),
+        (
+            "TAKE",
+            "\
+↯96_96_96⇡884736           # Init
+⊃(↙60_50_40)(↙¯60_¯50_¯40) # Run",
+        ),
+        (
+            "ROTATE",
+            "\
+=0◿2↯1024_1024⇡1048576 # Init
+↻300_¯7                # Run",
+        ),
+        (
+            "WINDOWS",
+            "\
+↯24_24_24_24◿7⇡331776 # Init
+◫3_3_3_3              # Run",
+        ),
+        (
+            "FIND",
+            "\
+↯24_24_24_24◿7⇡331776 # Init
+⌕↙3_3_3_3.            # Run",
+        ),
     ];
 
     const RUNS: usize = 20;
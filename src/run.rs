@@ -53,10 +53,18 @@ pub(crate) struct Runtime {
     pub unpack_boxes: bool,
     /// A limit on the execution duration in milliseconds
     execution_limit: Option<f64>,
+    /// A limit on the number of elements in arrays created by some operations
+    pub(crate) element_limit: Option<usize>,
+    /// A limit on how deeply operations may recurse through array axes
+    pub(crate) depth_limit: usize,
     /// The time at which execution started
     execution_start: f64,
     /// Whether to print the time taken to execute each instruction
     time_instrs: bool,
+    /// Whether to treat scalars as single-element lists where a list is required
+    pub(crate) promote_scalars: bool,
+    /// Whether NaN rows never match in `member` and `indexof`
+    pub(crate) nan_never_matches: bool,
     /// The time at which the last instruction was executed
     last_time: f64,
     /// Arguments passed from the command line
@@ -195,10 +203,14 @@ impl Default for Runtime {
             unpack_boxes: false,
             backend: Arc::new(SafeSys),
             time_instrs: false,
+            promote_scalars: false,
+            nan_never_matches: false,
             last_time: 0.0,
             cli_arguments: Vec::new(),
             cli_file_path: PathBuf::new(),
             execution_limit: None,
+            element_limit: None,
+            depth_limit: Uiua::DEFAULT_DEPTH_LIMIT,
             execution_start: 0.0,
             thread: ThisThread::default(),
             output_comments: HashMap::new(),
@@ -254,11 +266,48 @@ impl Uiua {
         self.rt.time_instrs = time_instrs;
         self
     }
+    /// Set whether scalars are treated as single-element lists by operations that
+    /// require a list, like `take` and `drop`
+    ///
+    /// This is off by default, so taking from or dropping from a scalar is an error.
+    pub fn promote_scalars(mut self, promote_scalars: bool) -> Self {
+        self.rt.promote_scalars = promote_scalars;
+        self
+    }
+    /// Set whether rows containing NaN never match in operations like `member` and `indexof`
+    ///
+    /// By default, NaN is equal to NaN in these operations, so a NaN can be found
+    /// and duplicate NaN rows are treated as the same row.
+    /// With this on, NaN follows IEEE 754 and is equal to nothing, not even itself.
+    pub fn nan_never_matches(mut self, nan_never_matches: bool) -> Self {
+        self.rt.nan_never_matches = nan_never_matches;
+        self
+    }
     /// Limit the execution duration
     pub fn with_execution_limit(mut self, limit: Duration) -> Self {
         self.rt.execution_limit = Some(limit.as_millis() as f64);
         self
     }
+    /// Limit the number of elements in arrays created by operations
+    /// whose result size is controlled by their arguments,
+    /// like `reshape`, `windows`, and `select`
+    ///
+    /// Without a limit, results are only limited by what can be allocated.
+    pub fn with_element_limit(mut self, limit: usize) -> Self {
+        self.rt.element_limit = Some(limit);
+        self
+    }
+    /// The default for [`Uiua::with_depth_limit`]
+    pub const DEFAULT_DEPTH_LIMIT: usize = 256;
+    /// Limit how many axes deep operations like `take`, `pick`, and `select`
+    /// may recurse, so that very high-rank arguments cause an error
+    /// rather than a stack overflow
+    ///
+    /// The default is [`Uiua::DEFAULT_DEPTH_LIMIT`].
+    pub fn with_depth_limit(mut self, limit: usize) -> Self {
+        self.rt.depth_limit = limit;
+        self
+    }
     /// Set the command line arguments
     pub fn with_args(mut self, args: Vec<String>) -> Self {
         self.rt.cli_arguments = args;
@@ -325,7 +374,11 @@ impl Uiua {
                 env.rt = Runtime {
                     backend: env.rt.backend.clone(),
                     execution_limit: env.rt.execution_limit,
+                    element_limit: env.rt.element_limit,
+                    depth_limit: env.rt.depth_limit,
                     time_instrs: env.rt.time_instrs,
+                    promote_scalars: env.rt.promote_scalars,
+                    nan_never_matches: env.rt.nan_never_matches,
                     output_comments: env.rt.output_comments.clone(),
                     ..Runtime::default()
                 };
@@ -1213,11 +1266,15 @@ code:
                 call_stack: Vec::new(),
                 unpack_boxes: self.rt.unpack_boxes,
                 time_instrs: self.rt.time_instrs,
+                promote_scalars: self.rt.promote_scalars,
+                nan_never_matches: self.rt.nan_never_matches,
                 last_time: self.rt.last_time,
                 cli_arguments: self.rt.cli_arguments.clone(),
                 cli_file_path: self.rt.cli_file_path.clone(),
                 backend: self.rt.backend.clone(),
                 execution_limit: self.rt.execution_limit,
+                element_limit: self.rt.element_limit,
+                depth_limit: self.rt.depth_limit,
                 execution_start: self.rt.execution_start,
                 output_comments: HashMap::new(),
                 memo: self.rt.memo.clone(),
@@ -972,6 +972,13 @@ impl Value {
             val => val,
         }
     }
+    /// Remove a single layer of boxing from a reference
+    pub(crate) fn unboxed_ref(&self) -> &Self {
+        match self.as_box() {
+            Some(boxed) => &boxed.0,
+            None => self,
+        }
+    }
     /// Turn the value into a scalar box if it is not one already
     pub fn box_if_not(&mut self) {
         match &mut *self {